@@ -1,30 +1,281 @@
 use super::{
     heuristics::{MAX_PLAYER, MIN_PLAYER},
-    Heuristic, Strategy, TreeVisitingStrategy,
+    move_ordering::{MoveOrdering, OrderingState},
+    transposition::{Bound, TableEntry, TranspositionTable, DEFAULT_TABLE_SIZE},
+    Evaluator, SearchResult, Strategy, TreeVisitingStrategy,
 };
-use crate::game::{GameState, Position};
+use crate::game::{bitboard as bb, GameState, Position};
 use std::{
     cmp::Ordering,
     fmt::{self, Display},
-    sync::atomic::{self, AtomicU32},
+    iter, mem,
+    sync::{
+        atomic::{self, AtomicU32},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
 #[must_use]
 pub struct AlphaBeta {
-    heuristic: Heuristic,
+    evaluator: Arc<dyn Evaluator>,
     max_depth: u32,
+    table_size: usize,
+    ordering: MoveOrdering,
+    threads: usize,
+    fast_wins: bool,
     visited: AtomicU32,
 }
 
+/// Minimum depth at which a shallow search is used to find a move to try first.
+const SHALLOW_SEARCH_MIN_DEPTH: u32 = 4;
+const SHALLOW_SEARCH_REDUCTION: u32 = 2;
+
+/// State shared by all nodes visited during a single search.
+#[must_use]
+pub(super) struct SearchContext {
+    pub table: TranspositionTable,
+    pub deadline: Option<Instant>,
+    pub ordering: OrderingState,
+    root_discs: u32,
+    /// Contexts of additional threads used by the parallel root search.
+    helpers: Vec<SearchContext>,
+}
+
+impl SearchContext {
+    pub fn new(table_size: usize, ordering: MoveOrdering, deadline: Option<Instant>) -> Self {
+        Self {
+            table: TranspositionTable::new(table_size),
+            deadline,
+            ordering: OrderingState::new(ordering),
+            root_discs: 0,
+            helpers: Vec::new(),
+        }
+    }
+
+    /// Marks the given position as the root of the following search.
+    pub fn start(&mut self, root: &GameState) {
+        self.root_discs = root.occupied_bb().count_ones();
+    }
+
+    #[must_use]
+    pub fn ply(&self, gs: &GameState) -> usize {
+        // Every move places exactly one disc, passes are not counted as plies
+        (gs.occupied_bb().count_ones() - self.root_discs) as usize
+    }
+
+    #[must_use]
+    pub fn timed_out(&self) -> bool {
+        self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+
+    /// Follows the best moves of exact table entries, starting with the given root move.
+    #[must_use]
+    pub fn principal_variation(
+        &self,
+        gs: &GameState,
+        position: Position,
+        depth: u32,
+    ) -> Vec<Position> {
+        let mut pv = Vec::from([position]);
+        let mut gs = gs.make_move(position);
+        while (pv.len() as u32) < depth {
+            let Some(next) = self
+                .table
+                .probe(&gs)
+                .filter(|e| e.bound == Bound::Exact)
+                .and_then(|e| e.best_move)
+                .filter(|&p| bb::has(gs.move_bb(), p))
+            else {
+                break;
+            };
+            pv.push(next);
+            gs = gs.make_move(next);
+        }
+        pv
+    }
+}
+
 impl AlphaBeta {
-    pub const fn new(heuristic: Heuristic, max_depth: u32) -> Self {
+    /// Accepts a `Heuristic`, or any other evaluator as `Box<dyn Evaluator>` or `Arc<dyn Evaluator>`.
+    pub fn new(evaluator: impl Into<Arc<dyn Evaluator>>, max_depth: u32) -> Self {
         Self {
-            heuristic,
+            evaluator: evaluator.into(),
             max_depth,
+            table_size: DEFAULT_TABLE_SIZE,
+            ordering: MoveOrdering::ALL,
+            threads: 1,
+            fast_wins: false,
             visited: AtomicU32::new(0),
         }
     }
 
+    /// Sets the number of positions remembered in a single search, 0 disables the table.
+    pub const fn with_table_size(mut self, table_size: usize) -> Self {
+        self.table_size = table_size;
+        self
+    }
+
+    pub const fn with_ordering(mut self, ordering: MoveOrdering) -> Self {
+        self.ordering = ordering;
+        self
+    }
+
+    /// Splits the root moves between the given number of threads.
+    ///
+    /// Every thread searches a fixed subset of moves with its own tables,
+    /// so that the result only depends on the thread count and not on the scheduling.
+    pub const fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = if threads == 0 { 1 } else { threads };
+        self
+    }
+
+    /// Prefers faster wins and slower losses over the final disc difference.
+    pub const fn with_fast_wins(mut self, fast_wins: bool) -> Self {
+        self.fast_wins = fast_wins;
+        self
+    }
+
+    pub fn evaluator(&self) -> &dyn Evaluator {
+        self.evaluator.as_ref()
+    }
+
+    pub(super) fn new_context(&self, deadline: Option<Instant>) -> SearchContext {
+        let new = || SearchContext::new(self.table_size, self.ordering, deadline);
+        let mut ctx = new();
+        ctx.helpers = (1..self.threads).map(|_| new()).collect();
+        ctx
+    }
+
+    /// Searches the position to the given depth, returns `None` if the deadline has passed first.
+    #[must_use]
+    pub(super) fn search(
+        &self,
+        gs: &GameState,
+        depth: u32,
+        ctx: &mut SearchContext,
+    ) -> Option<(f64, Position)> {
+        if self.threads > 1 {
+            return self.parallel_search(gs, depth, ctx);
+        }
+
+        ctx.start(gs);
+        let (eval, pos) = self.alpha_beta(gs, depth, f64::NEG_INFINITY, f64::INFINITY, ctx)?;
+        Some((eval, pos.unwrap()))
+    }
+
+    /// Builds the search result for a finished search.
+    pub(super) fn search_result(
+        &self,
+        gs: &GameState,
+        (score, position): (f64, Position),
+        depth: u32,
+        ctx: &SearchContext,
+        (visited_before, elapsed): (u32, Duration),
+    ) -> SearchResult {
+        SearchResult {
+            position,
+            score,
+            principal_variation: ctx.principal_variation(gs, position, depth),
+            depth,
+            nodes: self.visited() - visited_before,
+            elapsed,
+        }
+    }
+
+    #[must_use]
+    fn parallel_search(
+        &self,
+        gs: &GameState,
+        depth: u32,
+        ctx: &mut SearchContext,
+    ) -> Option<(f64, Position)> {
+        // The root is shared by all workers, so it is counted here only once
+        self.visited.fetch_add(1, atomic::Ordering::Relaxed);
+        let hash_move = ctx.table.probe(gs).and_then(|e| e.best_move);
+        let moves = ctx.ordering.order(gs, 0, hash_move);
+
+        let mut helpers = mem::take(&mut ctx.helpers);
+        for helper in helpers.iter_mut() {
+            helper.deadline = ctx.deadline;
+        }
+        let workers = self.threads.min(moves.len());
+        let results = thread::scope(|s| {
+            iter::once(&mut *ctx)
+                .chain(helpers.iter_mut())
+                .take(workers)
+                .enumerate()
+                .map(|(worker, worker_ctx)| {
+                    let assigned = moves.iter().copied().enumerate().skip(worker);
+                    let assigned = assigned.step_by(workers).collect::<Vec<_>>();
+                    s.spawn(move || self.search_root_moves(gs, depth, &assigned, worker_ctx))
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Option<Vec<_>>>()
+        });
+        ctx.helpers = helpers;
+
+        // Ties are broken by the move order, same as in the sequential search
+        let (eval, index) = results?
+            .into_iter()
+            .reduce(
+                |best, (eval, index)| match (gs.turn(), eval.partial_cmp(&best.0).unwrap()) {
+                    (MAX_PLAYER, Ordering::Greater) | (MIN_PLAYER, Ordering::Less) => (eval, index),
+                    (_, Ordering::Equal) if index < best.1 => (eval, index),
+                    _ => best,
+                },
+            )
+            .unwrap();
+
+        let entry = TableEntry::from_window(
+            depth,
+            eval,
+            Some(moves[index]),
+            f64::NEG_INFINITY,
+            f64::INFINITY,
+        );
+        ctx.table.store(gs, entry);
+        Some((eval, moves[index]))
+    }
+
+    /// Searches a subset of root moves, returns the best evaluation together with the move index.
+    #[must_use]
+    fn search_root_moves(
+        &self,
+        gs: &GameState,
+        depth: u32,
+        moves: &[(usize, Position)],
+        ctx: &mut SearchContext,
+    ) -> Option<(f64, usize)> {
+        ctx.start(gs);
+
+        let mut alpha = f64::NEG_INFINITY;
+        let mut beta = f64::INFINITY;
+        let (mut best_eval, mut best_index) = match gs.turn() {
+            MAX_PLAYER => (f64::NEG_INFINITY, moves[0].0),
+            MIN_PLAYER => (f64::INFINITY, moves[0].0),
+        };
+        for &(index, position) in moves {
+            let (eval, _) =
+                self.alpha_beta(&gs.make_move(position), depth - 1, alpha, beta, ctx)?;
+            if matches!(
+                (gs.turn(), eval.partial_cmp(&best_eval).unwrap()),
+                (MAX_PLAYER, Ordering::Greater) | (MIN_PLAYER, Ordering::Less),
+            ) {
+                best_eval = eval;
+                best_index = index;
+            }
+            match gs.turn() {
+                MAX_PLAYER => alpha = alpha.max(eval),
+                MIN_PLAYER => beta = beta.min(eval),
+            }
+        }
+        Some((best_eval, best_index))
+    }
+
     #[must_use]
     fn alpha_beta(
         &self,
@@ -32,22 +283,53 @@ impl AlphaBeta {
         depth: u32,
         mut alpha: f64,
         mut beta: f64,
-    ) -> (f64, Option<Position>) {
+        ctx: &mut SearchContext,
+    ) -> Option<(f64, Option<Position>)> {
         self.visited.fetch_add(1, atomic::Ordering::Relaxed);
 
-        if let Some(outcome) = gs.outcome() {
-            return (outcome.evaluate(), None);
+        if gs.outcome().is_some() {
+            return Some((gs.final_score(self.fast_wins), None));
         }
 
         if depth == 0 {
-            return (self.heuristic.evaluate(gs), None);
+            return Some((self.evaluator.evaluate(gs), None));
+        }
+
+        // Leaves are not checked, since they make up most of the tree
+        if ctx.timed_out() {
+            return None;
+        }
+
+        let mut hash_move = None;
+        if let Some(entry) = ctx.table.probe(gs) {
+            if entry.depth >= depth {
+                match entry.bound {
+                    Bound::Exact => return Some((entry.score, entry.best_move)),
+                    Bound::Lower => alpha = alpha.max(entry.score),
+                    Bound::Upper => beta = beta.min(entry.score),
+                }
+                if beta <= alpha {
+                    return Some((entry.score, entry.best_move));
+                }
+            }
+            hash_move = entry.best_move;
+        }
+        if self.ordering.hash_move && hash_move.is_none() && depth >= SHALLOW_SEARCH_MIN_DEPTH {
+            (_, hash_move) =
+                self.alpha_beta(gs, depth - SHALLOW_SEARCH_REDUCTION, alpha, beta, ctx)?;
         }
+        let (alpha_orig, beta_orig) = (alpha, beta);
 
-        let mut moves = gs.moves();
-        let mut best_pos = moves.pop().unwrap();
-        let (mut best_eval, _) = self.alpha_beta(&gs.make_move(best_pos), depth - 1, alpha, beta);
+        let ply = ctx.ply(gs);
+        let moves = ctx.ordering.order(gs, ply, hash_move);
+        let mut best_pos = moves[0];
+        let mut best_eval = match gs.turn() {
+            MAX_PLAYER => f64::NEG_INFINITY,
+            MIN_PLAYER => f64::INFINITY,
+        };
         for position in moves {
-            let (eval, _) = self.alpha_beta(&gs.make_move(position), depth - 1, alpha, beta);
+            let (eval, _) =
+                self.alpha_beta(&gs.make_move(position), depth - 1, alpha, beta, ctx)?;
             if matches!(
                 (gs.turn(), eval.partial_cmp(&best_eval).unwrap()),
                 (MAX_PLAYER, Ordering::Greater) | (MIN_PLAYER, Ordering::Less),
@@ -62,29 +344,140 @@ impl AlphaBeta {
                 MIN_PLAYER => beta = beta.min(eval),
             }
             if beta <= alpha {
+                ctx.ordering.record_cutoff(ply, position, depth);
                 break;
             }
         }
-        (best_eval, Some(best_pos))
+
+        ctx.table.store(
+            gs,
+            TableEntry::from_window(depth, best_eval, Some(best_pos), alpha_orig, beta_orig),
+        );
+        Some((best_eval, Some(best_pos)))
     }
 }
 
 impl Display for AlphaBeta {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "αβ({}, {})", self.heuristic, self.max_depth)
+        write!(f, "αβ({}, {})", self.evaluator, self.max_depth)
     }
 }
 
 impl Strategy for AlphaBeta {
     fn decide(&self, gs: &GameState) -> Position {
-        let (_, pos) = self.alpha_beta(gs, self.max_depth, f64::NEG_INFINITY, f64::INFINITY);
-        pos.unwrap()
+        self.analyze(gs).position
     }
 }
 
 impl TreeVisitingStrategy for AlphaBeta {
-    #[must_use]
     fn visited(&self) -> u32 {
         self.visited.load(atomic::Ordering::Relaxed)
     }
+
+    fn analyze(&self, gs: &GameState) -> SearchResult {
+        let start = Instant::now();
+        let visited_before = self.visited();
+        let mut ctx = self.new_context(None);
+        let result = self.search(gs, self.max_depth, &mut ctx).unwrap();
+        let stats = (visited_before, start.elapsed());
+        self.search_result(gs, result, self.max_depth, &ctx, stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Heuristic;
+    use quickcheck_macros::quickcheck;
+
+    fn root_search(strategy: &AlphaBeta, gs: &GameState) -> f64 {
+        let mut ctx = strategy.new_context(None);
+        let (eval, _) = strategy.search(gs, strategy.max_depth, &mut ctx).unwrap();
+        eval
+    }
+
+    #[quickcheck]
+    fn transposition_table_keeps_root_score(gs: GameState) -> bool {
+        let with_table = AlphaBeta::new(Heuristic::Korman, 3);
+        let without_table = AlphaBeta::new(Heuristic::Korman, 3).with_table_size(0);
+        gs.outcome().is_some() || root_search(&with_table, &gs) == root_search(&without_table, &gs)
+    }
+
+    #[test]
+    fn transposition_table_reduces_visited_nodes() {
+        let gs = GameState::othello_initial();
+        let with_table = AlphaBeta::new(Heuristic::Korman, 6);
+        let without_table = AlphaBeta::new(Heuristic::Korman, 6).with_table_size(0);
+        let _ = with_table.decide(&gs);
+        let _ = without_table.decide(&gs);
+        assert!(with_table.visited() < without_table.visited());
+    }
+
+    #[quickcheck]
+    fn move_ordering_keeps_root_score(gs: GameState) -> bool {
+        let ordered = AlphaBeta::new(Heuristic::Korman, 3);
+        let unordered = AlphaBeta::new(Heuristic::Korman, 3).with_ordering(MoveOrdering::NONE);
+        gs.outcome().is_some() || root_search(&ordered, &gs) == root_search(&unordered, &gs)
+    }
+
+    #[test]
+    fn move_ordering_reduces_visited_nodes() {
+        let gs = GameState::othello_initial();
+        let ordered = AlphaBeta::new(Heuristic::Korman, 6);
+        let unordered = AlphaBeta::new(Heuristic::Korman, 6).with_ordering(MoveOrdering::NONE);
+        let _ = ordered.decide(&gs);
+        let _ = unordered.decide(&gs);
+        assert!(ordered.visited() < unordered.visited());
+    }
+
+    #[quickcheck]
+    fn parallel_search_keeps_root_score(gs: GameState) -> bool {
+        let sequential = AlphaBeta::new(Heuristic::Korman, 3);
+        let parallel = AlphaBeta::new(Heuristic::Korman, 3).with_threads(3);
+        gs.outcome().is_some() || root_search(&sequential, &gs) == root_search(&parallel, &gs)
+    }
+
+    #[test]
+    fn parallel_search_counts_the_root_once() {
+        let gs = GameState::othello_initial();
+        let sequential = AlphaBeta::new(Heuristic::Korman, 1);
+        let parallel = AlphaBeta::new(Heuristic::Korman, 1).with_threads(4);
+        let _ = sequential.decide(&gs);
+        let _ = parallel.decide(&gs);
+        assert_eq!(sequential.visited(), 5);
+        assert_eq!(parallel.visited(), 5);
+    }
+
+    #[quickcheck]
+    fn parallel_search_is_deterministic(gs: GameState) -> bool {
+        let first = AlphaBeta::new(Heuristic::Korman, 3).with_threads(4);
+        let second = AlphaBeta::new(Heuristic::Korman, 3).with_threads(4);
+        gs.outcome().is_some()
+            || (first.decide(&gs) == second.decide(&gs) && first.visited() == second.visited())
+    }
+
+    #[quickcheck]
+    fn final_scores_give_the_exact_disc_difference() -> bool {
+        use crate::{
+            ai::{endgame::SolveMode, heuristics::HEURISTIC_BOUND, EndgameSolver, Minimax},
+            game::{Player, BOARD_SQUARES},
+        };
+        let gs = GameState::random_state_between_inc(54, 60);
+        if gs.outcome().is_some() {
+            return true;
+        }
+        let exact = EndgameSolver::new(BOARD_SQUARES as u32, SolveMode::ExactScore)
+            .solve(&gs)
+            .unwrap();
+        let margin = match exact.player {
+            Player::Black => exact.score,
+            Player::White => -exact.score,
+        };
+
+        let depth = gs.empty_bb().count_ones() + 1;
+        let alpha_beta = AlphaBeta::new(Heuristic::Korman, depth).analyze(&gs).score;
+        let minimax = Minimax::new(Heuristic::Korman, depth).analyze(&gs).score;
+        let expected = margin as f64 + (margin.signum() as f64) * HEURISTIC_BOUND;
+        alpha_beta == expected && minimax == expected
+    }
 }
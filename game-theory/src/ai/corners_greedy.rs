@@ -1,10 +1,31 @@
 use super::strategy::Strategy;
-use crate::game::{bitboard as bb, GameState, Position};
-use rand::{seq::SliceRandom, thread_rng};
+use crate::{
+    game::{bitboard as bb, GameState, Position},
+    utils::rng::StrategyRng,
+};
+use rand::Rng;
 use std::fmt::{self, Display};
 
 #[derive(Default)]
-pub struct CornersGreedy;
+pub struct CornersGreedy {
+    rng: StrategyRng,
+}
+
+impl CornersGreedy {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            rng: StrategyRng::new(),
+        }
+    }
+
+    /// Draws the moves from its own generator with the given seed.
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StrategyRng::seeded(seed);
+        self
+    }
+}
 
 impl Display for CornersGreedy {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -17,12 +38,14 @@ impl Strategy for CornersGreedy {
         let valid_moves = gs.move_bb();
         let valid_corners = valid_moves & bb::CORNERS;
 
-        *bb::positions(if valid_corners != bb::EMPTY {
+        let candidates = if valid_corners != bb::EMPTY {
             valid_corners
         } else {
             valid_moves
-        })
-        .choose(&mut thread_rng())
-        .unwrap()
+        };
+        let index = self
+            .rng
+            .with(|rng| rng.gen_range(0..candidates.count_ones() as usize));
+        bb::bits(candidates).nth(index).unwrap()
     }
 }
@@ -0,0 +1,222 @@
+use crate::game::{
+    bitboard::{self as bb, Bitboard},
+    GameState, Player, Position, BOARD_SQUARES,
+};
+use std::{
+    fmt::{self, Display},
+    sync::atomic::{self, AtomicU32},
+};
+
+const QUADRANTS: [Bitboard; 4] = [
+    0x000000000F0F0F0F,
+    0x00000000F0F0F0F0,
+    0x0F0F0F0F00000000,
+    0xF0F0F0F000000000,
+];
+
+#[must_use]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolveMode {
+    /// Only prove whether the game is won, lost or drawn, which is much faster.
+    WinLossDraw,
+    /// Find the exact final disc difference.
+    ExactScore,
+}
+
+#[must_use]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EndgameResult {
+    pub mode: SolveMode,
+    /// Player to move in the solved position, the score is given from their perspective.
+    pub player: Player,
+    /// Final disc difference, or only its sign in the win/loss/draw mode.
+    pub score: i32,
+    /// Moves leading to the proven result, passes are not included.
+    pub principal_variation: Vec<Position>,
+}
+
+impl Display for EndgameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.score.signum(), self.mode) {
+            (0, _) => write!(f, "Draw")?,
+            (1, SolveMode::WinLossDraw) => write!(f, "{} wins", self.player)?,
+            (_, SolveMode::WinLossDraw) => write!(f, "{} wins", self.player.opponent())?,
+            (1, SolveMode::ExactScore) => write!(f, "{} wins by {}", self.player, self.score)?,
+            (_, SolveMode::ExactScore) => {
+                write!(f, "{} wins by {}", self.player.opponent(), -self.score)?
+            }
+        }
+        write!(f, " |")?;
+        for position in &self.principal_variation {
+            write!(f, " {position}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Perfect play solver for positions close to the end of the game.
+#[must_use]
+pub struct EndgameSolver {
+    max_empties: u32,
+    mode: SolveMode,
+    visited: AtomicU32,
+}
+
+impl EndgameSolver {
+    pub const fn new(max_empties: u32, mode: SolveMode) -> Self {
+        Self {
+            max_empties,
+            mode,
+            visited: AtomicU32::new(0),
+        }
+    }
+
+    #[must_use]
+    pub const fn max_empties(&self) -> u32 {
+        self.max_empties
+    }
+
+    #[must_use]
+    pub fn visited(&self) -> u32 {
+        self.visited.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Solves the position, returns `None` if there are more than `max_empties` empty squares.
+    #[must_use]
+    pub fn solve(&self, gs: &GameState) -> Option<EndgameResult> {
+        if gs.empty_bb().count_ones() > self.max_empties {
+            return None;
+        }
+
+        let bound = match self.mode {
+            SolveMode::WinLossDraw => 1,
+            // Window wider than any final difference, so that every score is exact
+            SolveMode::ExactScore => BOARD_SQUARES as i32 + 1,
+        };
+        let mut principal_variation = Vec::new();
+        let score = self.negamax(gs, -bound, bound, &mut principal_variation);
+        Some(EndgameResult {
+            mode: self.mode,
+            player: gs.turn(),
+            score,
+            principal_variation,
+        })
+    }
+
+    #[must_use]
+    fn negamax(&self, gs: &GameState, mut alpha: i32, beta: i32, pv: &mut Vec<Position>) -> i32 {
+        self.visited.fetch_add(1, atomic::Ordering::Relaxed);
+
+        if gs.outcome().is_some() {
+            let player = gs.turn();
+            let diff = gs.score_of(player) as i32 - gs.score_of(player.opponent()) as i32;
+            return match self.mode {
+                SolveMode::WinLossDraw => diff.signum(),
+                SolveMode::ExactScore => diff,
+            };
+        }
+
+        let mut best_score = i32::MIN;
+        let mut child_pv = Vec::new();
+        for (position, child) in Self::ordered_children(gs) {
+            child_pv.clear();
+            let score = if child.turn() == gs.turn() {
+                // Opponent had to pass
+                self.negamax(&child, alpha, beta, &mut child_pv)
+            } else {
+                -self.negamax(&child, -beta, -alpha, &mut child_pv)
+            };
+
+            if score > best_score {
+                best_score = score;
+                pv.clear();
+                pv.push(position);
+                pv.append(&mut child_pv);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best_score
+    }
+
+    /// Orders moves fastest-first (least opponent replies), ties broken by quadrant parity.
+    #[must_use]
+    fn ordered_children(gs: &GameState) -> Vec<(Position, GameState)> {
+        let empty = gs.empty_bb();
+        let mut children = gs
+            .moves()
+            .into_iter()
+            .map(|position| (position, gs.make_move(position)))
+            .collect::<Vec<_>>();
+        children.sort_by_cached_key(|(position, child)| {
+            let replies = if child.turn() == gs.turn() {
+                0
+            } else {
+                child.move_bb().count_ones()
+            };
+            let quadrant = QUADRANTS
+                .into_iter()
+                .find(|q| bb::has(*q, *position))
+                .unwrap();
+            let even_region = (quadrant & empty).count_ones().is_multiple_of(2);
+            (replies, even_region)
+        });
+        children
+    }
+}
+
+impl Display for EndgameSolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self.mode {
+            SolveMode::WinLossDraw => "WLD",
+            SolveMode::ExactScore => "Exact",
+        };
+        write!(f, "EG({mode}, {})", self.max_empties)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::rng;
+
+    fn final_diff(gs: &GameState, player: Player) -> i32 {
+        gs.score_of(player) as i32 - gs.score_of(player.opponent()) as i32
+    }
+
+    #[test]
+    fn solver_refuses_positions_with_too_many_empties() {
+        let solver = EndgameSolver::new(10, SolveMode::ExactScore);
+        assert_eq!(solver.solve(&GameState::othello_initial()), None);
+    }
+
+    #[test]
+    fn principal_variation_leads_to_the_exact_score() {
+        rng::seed(4);
+        let solver = EndgameSolver::new(BOARD_SQUARES as u32, SolveMode::ExactScore);
+        for _ in 0..100 {
+            let gs = GameState::random_state_between_inc(52, 60);
+            let result = solver.solve(&gs).unwrap();
+            let mut end = gs.clone();
+            for &position in &result.principal_variation {
+                end = end.make_move(position);
+            }
+            assert!(end.outcome().is_some(), "{gs}");
+            assert_eq!(final_diff(&end, result.player), result.score, "{gs}");
+        }
+    }
+
+    #[test]
+    fn win_loss_draw_agrees_with_exact_score() {
+        rng::seed(4);
+        let wld = EndgameSolver::new(BOARD_SQUARES as u32, SolveMode::WinLossDraw);
+        let exact = EndgameSolver::new(BOARD_SQUARES as u32, SolveMode::ExactScore);
+        for _ in 0..100 {
+            let gs = GameState::random_state_between_inc(50, 60);
+            let score = exact.solve(&gs).unwrap().score;
+            assert_eq!(wld.solve(&gs).unwrap().score, score.signum(), "{gs}");
+        }
+    }
+}
@@ -5,9 +5,11 @@ use crate::game::{
 };
 use std::{
     cmp::Ordering,
-    collections::{hash_map::DefaultHasher, HashSet},
+    collections::hash_map::DefaultHasher,
     fmt::{self, Display},
     hash::{Hash, Hasher},
+    ops::RangeInclusive,
+    sync::Arc,
 };
 
 pub const MAX_PLAYER: Player = Player::Black;
@@ -24,6 +26,45 @@ impl Outcome {
     }
 }
 
+/// Values of all heuristics are within this bound, decided games score beyond it.
+///
+/// Weighted squares reach it when one player holds every negative square of
+/// VAISHU&MUTHU, linear equations get close at the first move with `le162`,
+/// and corner closeness reaches 1.5 with all squares next to empty corners taken.
+pub const HEURISTIC_BOUND: f64 = 2.;
+
+impl GameState {
+    /// Scores a finished game by its final disc difference, positive values favor Black.
+    ///
+    /// Decided games score beyond every heuristic value, so that bigger wins and smaller
+    /// losses are preferred. With `prefer_fast`, games finished with more empty squares
+    /// come first, so that wins are reached as soon as possible and losses as late as possible.
+    #[must_use]
+    pub fn final_score(&self, prefer_fast: bool) -> f64 {
+        debug_assert!(self.outcome().is_some());
+        let margin = self.score_of(MAX_PLAYER) as f64 - self.score_of(MIN_PLAYER) as f64;
+        if margin == 0. {
+            return 0.;
+        }
+        // Every empty square is worth more than the biggest possible margin
+        let speed = if prefer_fast {
+            self.empty_bb().count_ones() as f64 * (BOARD_SQUARES + 1) as f64
+        } else {
+            0.
+        };
+        margin.signum() * (HEURISTIC_BOUND + speed) + margin
+    }
+}
+
+/// Static evaluation used by the tree searching strategies, its `Display` output names it.
+pub trait Evaluator: Display + Send + Sync {
+    /// Evaluates a position of an unfinished game, positive values favor Black.
+    ///
+    /// Values should stay within [-`HEURISTIC_BOUND`, `HEURISTIC_BOUND`], so that they never
+    /// reach those of finished games.
+    fn evaluate(&self, gs: &GameState) -> f64;
+}
+
 pub const LINEAR_WEIGHT_LEN: usize = 14;
 const LINEAR_COMPONENTS: [Heuristic; LINEAR_WEIGHT_LEN / 2] = [
     Heuristic::MaximumDisc,
@@ -43,8 +84,31 @@ pub fn linear_hash(weights: &[f64; LINEAR_WEIGHT_LEN]) -> u8 {
     state.finish() as u8
 }
 
+/// Weights of heuristics used in a range of move numbers.
+#[must_use]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phase {
+    pub moves: RangeInclusive<i32>,
+    pub factors: Vec<(f64, Heuristic)>,
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.moves != (1..=60) {
+            write!(f, "{}-{}: ", self.moves.start(), self.moves.end())?;
+        }
+        for (i, (weight, heuristic)) in self.factors.iter().enumerate() {
+            if i > 0 {
+                write!(f, " + ")?;
+            }
+            write!(f, "{weight}*{heuristic}")?;
+        }
+        Ok(())
+    }
+}
+
 #[non_exhaustive]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Heuristic {
     /// - First mention: Rosenbloom 1982
     /// - AKA: p, coin party, piece difference
@@ -54,6 +118,8 @@ pub enum Heuristic {
     /// - First mention: Maggs 1979
     /// - AKA: d, disk squares, weighted square, static heuristic
     Weighted(&'static str, &'static WeightMatrix),
+    /// Weighted squares with a matrix created at runtime, see `weights::parse_weights`.
+    LoadedWeighted(String, Box<WeightMatrix>),
     /// - First mention: Korman 2003
     /// - AKA: c, corner occupancy, corners
     CornersOwned,
@@ -79,6 +145,8 @@ pub enum Heuristic {
     Iago,
     /// - First mention: Korman 2003
     Korman,
+    /// Weighted average of other heuristics, the first phase containing the move number is used.
+    Composite(Box<[Phase]>),
 }
 
 impl Display for Heuristic {
@@ -88,6 +156,7 @@ impl Display for Heuristic {
             MaximumDisc => write!(f, "MaxD"),
             MinimumDisc => write!(f, "MinD"),
             Weighted(name, _) => write!(f, "W({name})"),
+            LoadedWeighted(name, _) => write!(f, "W({name})"),
             CornersOwned => write!(f, "CrOwn"),
             CornerCloseness => write!(f, "CrCls"),
             CurrentMobility => write!(f, "CurMob"),
@@ -98,10 +167,32 @@ impl Display for Heuristic {
             LinearEquations(m_and_b) => write!(f, "LinEq({:03})", linear_hash(m_and_b)),
             Iago => write!(f, "IAGO"),
             Korman => write!(f, "KORMAN"),
+            Composite(phases) => {
+                write!(f, "Mix(")?;
+                for (i, phase) in phases.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{phase}")?;
+                }
+                write!(f, ")")
+            }
         }
     }
 }
 
+impl Evaluator for Heuristic {
+    fn evaluate(&self, gs: &GameState) -> f64 {
+        Heuristic::evaluate(self, gs)
+    }
+}
+
+impl From<Heuristic> for Arc<dyn Evaluator> {
+    fn from(heuristic: Heuristic) -> Self {
+        Arc::new(heuristic)
+    }
+}
+
 impl Heuristic {
     pub const W_MAGGS: Self = Self::Weighted("MAGGS", &WEIGHTS_MAGGS);
     pub const W_VAISHU_MUTHU: Self = Self::Weighted("VAISHU&MUTHU", &WEIGHTS_VAISHU_MUTHU);
@@ -130,29 +221,19 @@ impl Heuristic {
     #[must_use]
     pub fn evaluate(&self, gs: &GameState) -> f64 {
         use Heuristic::*;
-        let mut max_bb = gs.bb_of(MAX_PLAYER);
-        let mut min_bb = gs.bb_of(MIN_PLAYER);
+        let max_bb = gs.bb_of(MAX_PLAYER);
+        let min_bb = gs.bb_of(MIN_PLAYER);
         match self {
             MaximumDisc => Self::ratio(gs.score_of(MAX_PLAYER), gs.score_of(MIN_PLAYER)),
             MinimumDisc => -&MaximumDisc.evaluate(gs),
-            Weighted(_, weights) => {
-                let mut total = 0.;
-                let mut max = 0.;
-                for i in 0..BOARD_SQUARES {
-                    total += ((max_bb & 1) as f64 - (min_bb & 1) as f64) * weights[i] as f64;
-                    max += weights[i].max(0) as f64;
-                    max_bb >>= 1;
-                    min_bb >>= 1;
-                }
-                total / max
-            }
+            Weighted(_, weights) => Self::weighted_squares(gs, weights),
+            LoadedWeighted(_, weights) => Self::weighted_squares(gs, weights),
             CornersOwned => {
                 let max_corners = (gs.bb_of(MAX_PLAYER) & bb::CORNERS).count_ones() as f64;
                 let min_corners = (gs.bb_of(MIN_PLAYER) & bb::CORNERS).count_ones() as f64;
                 (max_corners - min_corners) / 4.
             }
-            CornerCloseness => bb::positions(bb::CORNERS)
-                .into_iter()
+            CornerCloseness => bb::bits(bb::CORNERS)
                 .map(|p| {
                     if bb::has(gs.empty_bb(), p) {
                         let target = bb::neighbours(bb::from_pos(p));
@@ -207,7 +288,26 @@ impl Heuristic {
                     (100., Stability),
                 ],
             ),
+            Composite(phases) => phases
+                .iter()
+                .find(|phase| phase.moves.contains(&gs.move_number()))
+                .map_or(0., |phase| Self::weighted_average(gs, &phase.factors)),
+        }
+    }
+
+    #[must_use]
+    fn weighted_squares(gs: &GameState, weights: &WeightMatrix) -> f64 {
+        let mut max_bb = gs.bb_of(MAX_PLAYER);
+        let mut min_bb = gs.bb_of(MIN_PLAYER);
+        let mut total = 0.;
+        let mut max = 0.;
+        for &weight in weights {
+            total += ((max_bb & 1) as f64 - (min_bb & 1) as f64) * weight as f64;
+            max += weight.max(0) as f64;
+            max_bb >>= 1;
+            min_bb >>= 1;
         }
+        total / max
     }
 
     #[must_use]
@@ -247,55 +347,18 @@ impl Heuristic {
     fn stability_ratio(gs: &GameState, mask: Bitboard) -> f64 {
         let max_bb = gs.bb_of(MAX_PLAYER);
         let min_bb = gs.bb_of(MIN_PLAYER);
-        let stable = Self::stable_bb(gs) & mask;
+        let stable = bb::stable_discs(max_bb, min_bb) & mask;
         Self::ratio(
             (stable & max_bb).count_ones(),
             (stable & min_bb).count_ones(),
         )
     }
-
-    #[must_use]
-    fn stable_bb(gs: &GameState) -> Bitboard {
-        let mut queue = Vec::new();
-        let mut visited = HashSet::new();
-        let mut stable = bb::EMPTY;
-
-        let corners = gs.occupied_bb() & bb::CORNERS;
-        stable |= corners;
-        queue.extend(bb::positions(corners));
-
-        while let Some(source) = queue.pop() {
-            if visited.contains(&source) {
-                continue;
-            }
-            visited.insert(source);
-
-            for pos in source.neighbours() {
-                let mut is_stable = true;
-                for line in bb::diagonals(pos) {
-                    let neighbours = bb::positions(line);
-                    if neighbours.len() == 2
-                        && neighbours
-                            .into_iter()
-                            .all(|n| gs.at(n) != gs.at(pos) || !bb::has(stable, n))
-                    {
-                        is_stable = false;
-                    }
-                }
-                if is_stable {
-                    stable |= bb::from_pos(pos);
-                    queue.push(pos);
-                }
-            }
-        }
-
-        stable
-    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ai::weights::expand_compact;
     use quickcheck::Arbitrary;
     use quickcheck_macros::quickcheck;
 
@@ -308,6 +371,10 @@ mod tests {
                 Heuristic::W_MAGGS,
                 Heuristic::W_VAISHU_MUTHU,
                 Heuristic::W_KORMAN,
+                LoadedWeighted(
+                    "COMPACT".to_string(),
+                    Box::new(expand_compact([9, -1, 3, 2, -5, -1, 0, 1, 1, -2])),
+                ),
                 CornersOwned,
                 CornerCloseness,
                 CurrentMobility,
@@ -315,8 +382,23 @@ mod tests {
                 InternalStability,
                 EdgeStability,
                 Stability,
+                Heuristic::le051(),
+                Heuristic::le064(),
+                Heuristic::le148(),
+                Heuristic::le162(),
+                Heuristic::le215(),
                 Iago,
                 Korman,
+                Composite(Box::new([
+                    Phase {
+                        moves: 1..=20,
+                        factors: vec![(0.8, CornersOwned), (0.2, CurrentMobility)],
+                    },
+                    Phase {
+                        moves: 1..=60,
+                        factors: vec![(1., Stability), (3., Heuristic::W_MAGGS)],
+                    },
+                ])),
             ])
             .cloned()
             .unwrap()
@@ -325,7 +407,7 @@ mod tests {
 
     #[quickcheck]
     fn all_heuristics_are_normalized(heuristic: Heuristic, gs: GameState) -> bool {
-        (-1. ..=1.).contains(&heuristic.evaluate(&gs))
+        (-HEURISTIC_BOUND..=HEURISTIC_BOUND).contains(&heuristic.evaluate(&gs))
     }
 
     #[quickcheck]
@@ -342,4 +424,72 @@ mod tests {
     fn cmac_returns_nonnegative_coeffs(gs: GameState) -> bool {
         Heuristic::cmac(gs.move_number()) >= 0.
     }
+
+    #[quickcheck]
+    fn final_scores_rank_above_heuristics(heuristic: Heuristic, gs: GameState) -> bool {
+        let mut end = gs.clone();
+        while end.outcome().is_none() {
+            end = end.make_move(end.moves()[0]);
+        }
+        let (slow, fast) = (end.final_score(false), end.final_score(true));
+        match end.outcome().unwrap() {
+            Outcome::Draw => slow == 0. && fast == 0.,
+            Outcome::Winner(winner) => {
+                let sign = if winner == MAX_PLAYER { 1. } else { -1. };
+                let value = heuristic.evaluate(&gs).abs();
+                sign * slow > value && sign * fast >= sign * slow
+            }
+        }
+    }
+
+    #[test]
+    fn final_scores_order_by_margin_then_speed() {
+        let finished = |black: u64, white: u64| {
+            let gs = GameState::from_discs(black, white, Some(MAX_PLAYER)).unwrap();
+            assert!(gs.outcome().is_some());
+            gs
+        };
+        let small_win = finished(0b1111, 0);
+        let big_win = finished(0b111111, 0);
+        let loss = finished(0, 0b1111);
+        assert!(big_win.final_score(false) > small_win.final_score(false));
+        assert!(small_win.final_score(true) > big_win.final_score(true));
+        assert!(loss.final_score(false) < -HEURISTIC_BOUND);
+        // Slower losses are preferred, so a game lost with fewer empty squares scores higher
+        let slow_loss = finished(0, 0b111111);
+        assert!(slow_loss.final_score(true) > loss.final_score(true));
+        assert!(slow_loss.final_score(false) < loss.final_score(false));
+    }
+
+    #[test]
+    fn extreme_boards_stay_within_bound() {
+        let narrowest_win = GameState::from_discs(0b111, 0b11 << 62, Some(MAX_PLAYER)).unwrap();
+        let narrowest_win = narrowest_win.final_score(false);
+        let within_bound = |heuristic: &Heuristic, max_bb, min_bb| {
+            let gs = GameState::from_discs(max_bb, min_bb, Some(MAX_PLAYER)).unwrap();
+            let value = heuristic.evaluate(&gs).abs();
+            assert!(value <= HEURISTIC_BOUND, "{heuristic}: {value}");
+            assert!(value < narrowest_win);
+        };
+
+        let matrices = [
+            (Heuristic::W_MAGGS, WEIGHTS_MAGGS),
+            (Heuristic::W_VAISHU_MUTHU, WEIGHTS_VAISHU_MUTHU),
+            (Heuristic::W_KORMAN, WEIGHTS_KORMAN),
+        ];
+        for (heuristic, weights) in matrices {
+            let squares_with = |wanted: fn(i32) -> bool| {
+                (0..BOARD_SQUARES)
+                    .filter(|&i| wanted(weights[i]))
+                    .fold(0, |bb, i| bb | 1 << i)
+            };
+            let (positive, negative) = (squares_with(|w| w > 0), squares_with(|w| w < 0));
+            within_bound(&heuristic, positive, negative);
+            within_bound(&heuristic, negative, positive);
+        }
+
+        let next_to_corners = bb::neighbours(bb::CORNERS) & !bb::CORNERS;
+        within_bound(&Heuristic::CornerCloseness, 0, next_to_corners);
+        within_bound(&Heuristic::CornerCloseness, next_to_corners, 0);
+    }
 }
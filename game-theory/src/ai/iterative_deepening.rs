@@ -0,0 +1,117 @@
+use super::{AlphaBeta, Evaluator, MoveOrdering, SearchResult, Strategy, TreeVisitingStrategy};
+use crate::game::{GameState, Position};
+use std::{
+    fmt::{self, Display},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+#[must_use]
+pub struct IterativeDeepening {
+    search: AlphaBeta,
+    time_limit: Duration,
+}
+
+impl IterativeDeepening {
+    pub fn new(evaluator: impl Into<Arc<dyn Evaluator>>, time_limit: Duration) -> Self {
+        Self {
+            // Depth passed here is never used, every iteration sets its own
+            search: AlphaBeta::new(evaluator, 1),
+            time_limit,
+        }
+    }
+
+    pub fn with_table_size(mut self, table_size: usize) -> Self {
+        self.search = self.search.with_table_size(table_size);
+        self
+    }
+
+    pub fn with_ordering(mut self, ordering: MoveOrdering) -> Self {
+        self.search = self.search.with_ordering(ordering);
+        self
+    }
+
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.search = self.search.with_threads(threads);
+        self
+    }
+
+    pub fn with_fast_wins(mut self, fast_wins: bool) -> Self {
+        self.search = self.search.with_fast_wins(fast_wins);
+        self
+    }
+}
+
+impl Display for IterativeDeepening {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ID({}, {}ms)",
+            self.search.evaluator(),
+            self.time_limit.as_millis()
+        )
+    }
+}
+
+impl Strategy for IterativeDeepening {
+    fn decide(&self, gs: &GameState) -> Position {
+        self.analyze(gs).position
+    }
+}
+
+impl TreeVisitingStrategy for IterativeDeepening {
+    fn visited(&self) -> u32 {
+        self.search.visited()
+    }
+
+    /// Reports the result of the deepest fully searched depth.
+    fn analyze(&self, gs: &GameState) -> SearchResult {
+        let start = Instant::now();
+        let visited_before = self.visited();
+        let deadline = start + self.time_limit;
+        let mut ctx = self.search.new_context(None);
+
+        // First iteration is always completed, so that there is a move to return
+        let mut best = self.search.search(gs, 1, &mut ctx).unwrap();
+        ctx.deadline = Some(deadline);
+        let mut depth = 1;
+
+        // Searching deeper than the number of empty squares would give the same result
+        let max_depth = gs.empty_bb().count_ones();
+        while depth < max_depth {
+            match self.search.search(gs, depth + 1, &mut ctx) {
+                Some(result) => {
+                    best = result;
+                    depth += 1;
+                }
+                None => break,
+            }
+        }
+
+        let stats = (visited_before, start.elapsed());
+        self.search.search_result(gs, best, depth, &ctx, stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Heuristic;
+    use quickcheck_macros::quickcheck;
+
+    #[quickcheck]
+    fn iterative_deepening_returns_a_valid_move(gs: GameState) -> bool {
+        let strategy = IterativeDeepening::new(Heuristic::Korman, Duration::from_millis(5));
+        gs.outcome().is_some() || gs.moves().contains(&strategy.decide(&gs))
+    }
+
+    #[test]
+    fn iterative_deepening_stops_at_the_end_of_the_game() {
+        let gs = GameState::random_state_between_inc(56, 56);
+        let strategy = IterativeDeepening::new(Heuristic::Korman, Duration::from_secs(60));
+        if gs.outcome().is_none() {
+            let result = strategy.analyze(&gs);
+            assert_eq!(result.depth, gs.empty_bb().count_ones());
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use super::{
+    heuristics::MAX_PLAYER, Evaluator, RandomMove, SearchResult, Strategy, TreeVisitingStrategy,
+};
+use crate::{
+    game::{GameState, Outcome, Player, Position},
+    utils::rng::StrategyRng,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use std::{
+    fmt::{self, Display},
+    sync::{
+        atomic::{self, AtomicU32},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+pub const DEFAULT_EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+/// Chance of a heuristic playout making a random move instead of the greedy one.
+const PLAYOUT_EPSILON: f64 = 0.2;
+
+#[must_use]
+#[derive(Clone)]
+pub enum Playout {
+    /// Both players play using `RandomMove`.
+    Random,
+    /// Both players mostly pick the move with the best heuristic value.
+    Heuristic(Arc<dyn Evaluator>),
+}
+
+impl Display for Playout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Playout::Random => write!(f, "Rand"),
+            Playout::Heuristic(heuristic) => heuristic.fmt(f),
+        }
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, Copy)]
+pub enum Budget {
+    Iterations(u32),
+    Time(Duration),
+}
+
+impl Display for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Budget::Iterations(n) => write!(f, "{n}"),
+            Budget::Time(duration) => write!(f, "{}ms", duration.as_millis()),
+        }
+    }
+}
+
+struct Node {
+    state: GameState,
+    position: Option<Position>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<Position>,
+    visits: u32,
+    /// Sum of rewards from the perspective of the player, who made the move leading here.
+    reward: f64,
+}
+
+impl Node {
+    fn new(
+        state: GameState,
+        position: Option<Position>,
+        parent: Option<usize>,
+        rng: &mut StdRng,
+    ) -> Self {
+        let mut untried = state.moves();
+        untried.shuffle(rng);
+        Self {
+            state,
+            position,
+            parent,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            reward: 0.,
+        }
+    }
+}
+
+/// Monte Carlo Tree Search using the UCT selection policy.
+#[must_use]
+pub struct Mcts {
+    playout: Playout,
+    budget: Budget,
+    exploration: f64,
+    visited: AtomicU32,
+    rng: StrategyRng,
+}
+
+impl Mcts {
+    pub const fn new(playout: Playout, budget: Budget) -> Self {
+        Self {
+            playout,
+            budget,
+            exploration: DEFAULT_EXPLORATION,
+            visited: AtomicU32::new(0),
+            rng: StrategyRng::new(),
+        }
+    }
+
+    pub const fn with_exploration(mut self, exploration: f64) -> Self {
+        self.exploration = exploration;
+        self
+    }
+
+    /// Draws the move order and playouts from its own generator with the given seed.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StrategyRng::seeded(seed);
+        self
+    }
+
+    #[must_use]
+    fn uct(&self, node: &Node, parent_visits: u32) -> f64 {
+        let visits = node.visits as f64;
+        node.reward / visits + self.exploration * ((parent_visits as f64).ln() / visits).sqrt()
+    }
+
+    #[must_use]
+    fn select(&self, tree: &[Node]) -> usize {
+        let mut current = 0;
+        while tree[current].untried.is_empty() && !tree[current].children.is_empty() {
+            let parent_visits = tree[current].visits;
+            current = *tree[current]
+                .children
+                .iter()
+                .max_by(|&&a, &&b| {
+                    self.uct(&tree[a], parent_visits)
+                        .total_cmp(&self.uct(&tree[b], parent_visits))
+                })
+                .unwrap();
+        }
+        current
+    }
+
+    fn playout_move(&self, gs: &GameState, rng: &mut StdRng) -> Position {
+        match &self.playout {
+            Playout::Random => RandomMove::choose(gs, rng),
+            Playout::Heuristic(_) if rng.gen_bool(PLAYOUT_EPSILON) => RandomMove::choose(gs, rng),
+            Playout::Heuristic(heuristic) => {
+                let sign = if gs.turn() == MAX_PLAYER { 1. } else { -1. };
+                gs.moves()
+                    .into_iter()
+                    .max_by(|&a, &b| {
+                        let a = sign * heuristic.evaluate(&gs.make_move(a));
+                        let b = sign * heuristic.evaluate(&gs.make_move(b));
+                        a.total_cmp(&b)
+                    })
+                    .unwrap()
+            }
+        }
+    }
+
+    fn simulate(&self, gs: &GameState, rng: &mut StdRng) -> Outcome {
+        let mut gs = gs.clone();
+        loop {
+            if let Some(outcome) = gs.outcome() {
+                return outcome;
+            }
+            gs = gs.make_move(self.playout_move(&gs, rng));
+        }
+    }
+
+    fn iterate(&self, tree: &mut Vec<Node>, rng: &mut StdRng) {
+        let mut current = self.select(tree);
+
+        // Expansion
+        if let Some(position) = tree[current].untried.pop() {
+            let state = tree[current].state.make_move(position);
+            tree.push(Node::new(state, Some(position), Some(current), rng));
+            let child = tree.len() - 1;
+            tree[current].children.push(child);
+            current = child;
+            self.visited.fetch_add(1, atomic::Ordering::Relaxed);
+        }
+
+        // Simulation
+        let outcome = self.simulate(&tree[current].state, rng);
+
+        // Backpropagation
+        let mut node = Some(current);
+        while let Some(index) = node {
+            let parent = tree[index].parent;
+            tree[index].visits += 1;
+            if let Some(parent) = parent {
+                let mover = tree[parent].state.turn();
+                tree[index].reward += Self::reward(&outcome, mover);
+            }
+            node = parent;
+        }
+    }
+
+    #[must_use]
+    fn reward(outcome: &Outcome, player: Player) -> f64 {
+        match outcome {
+            Outcome::Winner(winner) if *winner == player => 1.,
+            Outcome::Winner(_) => 0.,
+            Outcome::Draw => 0.5,
+        }
+    }
+}
+
+impl Display for Mcts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MCTS({}, {})", self.playout, self.budget)
+    }
+}
+
+impl Strategy for Mcts {
+    fn decide(&self, gs: &GameState) -> Position {
+        self.analyze(gs).position
+    }
+}
+
+impl TreeVisitingStrategy for Mcts {
+    fn visited(&self) -> u32 {
+        self.visited.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Reports the win rate of the chosen move scaled to -1..=1 and the most visited line.
+    fn analyze(&self, gs: &GameState) -> SearchResult {
+        let start = Instant::now();
+        let visited_before = self.visited();
+        let tree = self.rng.with(|rng| {
+            let mut tree = Vec::from([Node::new(gs.clone(), None, None, rng)]);
+            self.visited.fetch_add(1, atomic::Ordering::Relaxed);
+
+            match self.budget {
+                Budget::Iterations(n) => (0..n.max(1)).for_each(|_| self.iterate(&mut tree, rng)),
+                Budget::Time(limit) => {
+                    // At least one iteration, so that the root has a child
+                    self.iterate(&mut tree, rng);
+                    while start.elapsed() < limit {
+                        self.iterate(&mut tree, rng);
+                    }
+                }
+            }
+            tree
+        });
+
+        let most_visited = |node: usize| {
+            tree[node]
+                .children
+                .iter()
+                .copied()
+                .max_by_key(|&child| tree[child].visits)
+        };
+        let best = most_visited(0).unwrap();
+        let win_rate = tree[best].reward / tree[best].visits as f64;
+        let sign = if gs.turn() == MAX_PLAYER { 1. } else { -1. };
+
+        let mut principal_variation = Vec::new();
+        let mut node = Some(best);
+        while let Some(index) = node {
+            principal_variation.extend(tree[index].position);
+            node = most_visited(index);
+        }
+
+        SearchResult {
+            position: principal_variation[0],
+            score: sign * (2. * win_rate - 1.),
+            depth: principal_variation.len() as u32,
+            principal_variation,
+            nodes: self.visited() - visited_before,
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Heuristic;
+    use quickcheck_macros::quickcheck;
+
+    #[quickcheck]
+    fn mcts_returns_a_valid_move(gs: GameState) -> bool {
+        let strategy = Mcts::new(Playout::Random, Budget::Iterations(20));
+        gs.outcome().is_some() || gs.moves().contains(&strategy.decide(&gs))
+    }
+
+    #[quickcheck]
+    fn heuristic_playouts_return_a_valid_move(gs: GameState) -> bool {
+        let strategy = Mcts::new(
+            Playout::Heuristic(Heuristic::MaximumDisc.into()),
+            Budget::Iterations(5),
+        );
+        gs.outcome().is_some() || gs.moves().contains(&strategy.decide(&gs))
+    }
+
+    #[quickcheck]
+    fn same_seed_gives_the_same_search(seed: u64) -> bool {
+        let gs = GameState::othello_initial();
+        let search = || {
+            let strategy = Mcts::new(Playout::Random, Budget::Iterations(50)).with_seed(seed);
+            strategy.analyze(&gs).principal_variation
+        };
+        search() == search()
+    }
+
+    #[test]
+    fn display_is_similar_to_other_tree_strategies() {
+        let strategy = Mcts::new(
+            Playout::Heuristic(Heuristic::Korman.into()),
+            Budget::Time(Duration::from_millis(100)),
+        );
+        assert_eq!(strategy.to_string(), "MCTS(KORMAN, 100ms)");
+        let strategy = Mcts::new(Playout::Random, Budget::Iterations(1000));
+        assert_eq!(strategy.to_string(), "MCTS(Rand, 1000)");
+    }
+}
@@ -1,75 +1,107 @@
 use super::{
     heuristics::{MAX_PLAYER, MIN_PLAYER},
-    Heuristic, Strategy, TreeVisitingStrategy,
+    Evaluator, SearchResult, Strategy, TreeVisitingStrategy,
 };
 use crate::game::{GameState, Position};
 use std::{
     cmp::Ordering,
     fmt::{self, Display},
-    sync::atomic::{self, AtomicU32},
+    sync::{
+        atomic::{self, AtomicU32},
+        Arc,
+    },
+    time::Instant,
 };
 
 #[must_use]
 pub struct Minimax {
-    heuristic: Heuristic,
+    evaluator: Arc<dyn Evaluator>,
     max_depth: u32,
+    fast_wins: bool,
     visited: AtomicU32,
 }
 
 impl Minimax {
-    pub const fn new(heuristic: Heuristic, max_depth: u32) -> Self {
+    pub fn new(evaluator: impl Into<Arc<dyn Evaluator>>, max_depth: u32) -> Self {
         Self {
-            heuristic,
+            evaluator: evaluator.into(),
             max_depth,
+            fast_wins: false,
             visited: AtomicU32::new(0),
         }
     }
 
+    /// Prefers faster wins and slower losses over the final disc difference.
+    pub const fn with_fast_wins(mut self, fast_wins: bool) -> Self {
+        self.fast_wins = fast_wins;
+        self
+    }
+
+    /// Returns the evaluation, filling `pv` with the expected line of play.
     #[must_use]
-    fn minimax(&self, gs: &GameState, depth: u32) -> (f64, Option<Position>) {
+    fn minimax(&self, gs: &GameState, depth: u32, pv: &mut Vec<Position>) -> f64 {
         self.visited.fetch_add(1, atomic::Ordering::Relaxed);
+        pv.clear();
 
-        if let Some(outcome) = gs.outcome() {
-            return (outcome.evaluate(), None);
+        if gs.outcome().is_some() {
+            return gs.final_score(self.fast_wins);
         }
 
         if depth == 0 {
-            return (self.heuristic.evaluate(gs), None);
+            return self.evaluator.evaluate(gs);
         }
 
         let mut moves = gs.moves();
-        let mut best_pos = moves.pop().unwrap();
-        let (mut best_eval, _) = self.minimax(&gs.make_move(best_pos), depth - 1);
+        let mut child_pv = Vec::new();
+        let first = moves.pop().unwrap();
+        let mut best_eval = self.minimax(&gs.make_move(first), depth - 1, &mut child_pv);
+        pv.push(first);
+        pv.append(&mut child_pv);
         for position in moves {
-            let (eval, _) = self.minimax(&gs.make_move(position), depth - 1);
+            let eval = self.minimax(&gs.make_move(position), depth - 1, &mut child_pv);
             if matches!(
                 (gs.turn(), eval.partial_cmp(&best_eval).unwrap()),
                 (MAX_PLAYER, Ordering::Greater) | (MIN_PLAYER, Ordering::Less),
             ) {
                 best_eval = eval;
-                best_pos = position;
+                pv.clear();
+                pv.push(position);
+                pv.append(&mut child_pv);
             }
         }
-        (best_eval, Some(best_pos))
+        best_eval
     }
 }
 
 impl Display for Minimax {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "MM({}, {})", self.heuristic, self.max_depth)
+        write!(f, "MM({}, {})", self.evaluator, self.max_depth)
     }
 }
 
 impl Strategy for Minimax {
     fn decide(&self, gs: &GameState) -> Position {
-        let (_, pos) = self.minimax(gs, self.max_depth);
-        pos.unwrap()
+        self.analyze(gs).position
     }
 }
 
 impl TreeVisitingStrategy for Minimax {
-    #[must_use]
     fn visited(&self) -> u32 {
         self.visited.load(atomic::Ordering::Relaxed)
     }
+
+    fn analyze(&self, gs: &GameState) -> SearchResult {
+        let start = Instant::now();
+        let visited_before = self.visited();
+        let mut principal_variation = Vec::new();
+        let score = self.minimax(gs, self.max_depth, &mut principal_variation);
+        SearchResult {
+            position: principal_variation[0],
+            score,
+            principal_variation,
+            depth: self.max_depth,
+            nodes: self.visited() - visited_before,
+            elapsed: start.elapsed(),
+        }
+    }
 }
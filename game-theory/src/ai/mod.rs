@@ -1,20 +1,33 @@
 mod alpha_beta;
 mod corners_greedy;
+pub mod endgame;
 mod first_move;
 pub mod heuristics;
+mod iterative_deepening;
+pub mod mcts;
 mod minimax;
+mod move_ordering;
+pub mod opening_book;
 mod player_input;
+mod pvs;
 mod random_move;
 mod score_greedy;
 pub mod strategy;
-mod weights;
+pub mod transposition;
+pub mod weights;
 
 pub use alpha_beta::AlphaBeta;
 pub use corners_greedy::CornersGreedy;
+pub use endgame::EndgameSolver;
 pub use first_move::FirstMove;
-pub use heuristics::Heuristic;
+pub use heuristics::{Evaluator, Heuristic};
+pub use iterative_deepening::IterativeDeepening;
+pub use mcts::Mcts;
 pub use minimax::Minimax;
+pub use move_ordering::MoveOrdering;
+pub use opening_book::OpeningBook;
 pub use player_input::PlayerInput;
+pub use pvs::Pvs;
 pub use random_move::RandomMove;
 pub use score_greedy::ScoreGreedy;
-pub use strategy::{Strategy, TreeVisitingStrategy};
+pub use strategy::{SearchResult, Strategy, TreeVisitingStrategy};
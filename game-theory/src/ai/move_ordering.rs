@@ -0,0 +1,186 @@
+use crate::game::{
+    bitboard::{self as bb, Bitboard},
+    GameState, Position, BOARD_SQUARES,
+};
+use std::fmt::{self, Display};
+
+const KILLER_SLOTS: usize = 2;
+
+// Sources are ranked by their weight: hash move > killers > static order > history
+const HASH_MOVE_WEIGHT: u32 = 1 << 24;
+const KILLER_WEIGHTS: [u32; KILLER_SLOTS] = [1 << 23, 1 << 22];
+const STATIC_WEIGHT: u32 = 1 << 20;
+const HISTORY_LIMIT: u32 = STATIC_WEIGHT - 1;
+
+#[must_use]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MoveOrdering {
+    /// Try the best move remembered by the transposition table or found by a shallow search first.
+    pub hash_move: bool,
+    /// Try the moves which caused cutoffs in sibling nodes first.
+    pub killers: bool,
+    /// Prefer moves which caused cutoffs anywhere in the tree.
+    pub history: bool,
+    /// Try corners first and X-squares last.
+    pub static_order: bool,
+}
+
+impl MoveOrdering {
+    pub const NONE: Self = Self {
+        hash_move: false,
+        killers: false,
+        history: false,
+        static_order: false,
+    };
+
+    pub const ALL: Self = Self {
+        hash_move: true,
+        killers: true,
+        history: true,
+        static_order: true,
+    };
+
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.hash_move || self.killers || self.history || self.static_order
+    }
+}
+
+impl Display for MoveOrdering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sources = [
+            (self.hash_move, "hash"),
+            (self.killers, "killers"),
+            (self.history, "history"),
+            (self.static_order, "static"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect::<Vec<_>>();
+
+        if sources.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", sources.join("+"))
+        }
+    }
+}
+
+/// Killer moves and history scores gathered during a single search.
+#[must_use]
+pub(super) struct OrderingState {
+    ordering: MoveOrdering,
+    killers: Vec<[Option<Position>; KILLER_SLOTS]>,
+    history: [u32; BOARD_SQUARES],
+}
+
+impl OrderingState {
+    pub fn new(ordering: MoveOrdering) -> Self {
+        Self {
+            ordering,
+            killers: Vec::new(),
+            history: [0; BOARD_SQUARES],
+        }
+    }
+
+    /// Returns the moves in the order, in which they should be searched.
+    #[must_use]
+    pub fn order(&self, gs: &GameState, ply: usize, hash_move: Option<Position>) -> Vec<Position> {
+        let mut moves = gs.moves();
+        if !self.ordering.is_enabled() {
+            // Historical order: last generated move is searched first
+            moves.rotate_right(1);
+            return moves;
+        }
+
+        let killers = self.killers.get(ply).copied().unwrap_or_default();
+        moves.sort_by_cached_key(|&position| {
+            let mut score = 0;
+            if self.ordering.hash_move && hash_move == Some(position) {
+                score += HASH_MOVE_WEIGHT;
+            }
+            if self.ordering.killers {
+                for (killer, weight) in killers.iter().zip(KILLER_WEIGHTS) {
+                    if *killer == Some(position) {
+                        score += weight;
+                    }
+                }
+            }
+            if self.ordering.static_order {
+                score += STATIC_WEIGHT * static_rank(bb::from_pos(position));
+            }
+            if self.ordering.history {
+                score += self.history[position.index()];
+            }
+            u32::MAX - score
+        });
+        moves
+    }
+
+    /// Remembers a move which caused a cutoff.
+    pub fn record_cutoff(&mut self, ply: usize, position: Position, depth: u32) {
+        if self.ordering.killers {
+            if self.killers.len() <= ply {
+                self.killers.resize(ply + 1, [None; KILLER_SLOTS]);
+            }
+            let slots = &mut self.killers[ply];
+            if slots[0] != Some(position) {
+                slots[1] = slots[0];
+                slots[0] = Some(position);
+            }
+        }
+
+        if self.ordering.history {
+            let entry = &mut self.history[position.index()];
+            *entry = entry.saturating_add(depth * depth).min(HISTORY_LIMIT);
+        }
+    }
+}
+
+#[must_use]
+const fn static_rank(position: Bitboard) -> u32 {
+    if position & bb::CORNERS != bb::EMPTY {
+        2
+    } else if position & bb::X_SQUARES != bb::EMPTY {
+        0
+    } else {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::p;
+
+    #[test]
+    fn disabled_ordering_keeps_historical_order() {
+        let gs = GameState::othello_initial();
+        let state = OrderingState::new(MoveOrdering::NONE);
+        let mut expected = gs.moves();
+        expected.rotate_right(1);
+        assert_eq!(state.order(&gs, 0, Some(p("D3"))), expected);
+    }
+
+    #[test]
+    fn hash_move_and_killers_are_tried_first() {
+        let gs = GameState::othello_initial();
+        let mut state = OrderingState::new(MoveOrdering::ALL);
+        state.record_cutoff(0, p("C4"), 1);
+        let moves = state.order(&gs, 0, Some(p("F5")));
+        assert_eq!(&moves[..2], &[p("F5"), p("C4")]);
+    }
+
+    #[test]
+    fn static_order_prefers_corners_and_avoids_x_squares() {
+        assert_eq!(static_rank(bb::from_pos(p("A1"))), 2);
+        assert_eq!(static_rank(bb::from_pos(p("D3"))), 1);
+        assert_eq!(static_rank(bb::from_pos(p("G7"))), 0);
+    }
+
+    #[test]
+    fn display_lists_enabled_sources() {
+        assert_eq!(MoveOrdering::NONE.to_string(), "none");
+        assert_eq!(MoveOrdering::ALL.to_string(), "hash+killers+history+static");
+    }
+}
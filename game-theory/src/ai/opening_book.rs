@@ -0,0 +1,277 @@
+use super::Strategy;
+use crate::game::{
+    record::{GameRecord, RecordError},
+    zobrist::ZobristBuildHasher,
+    GameState, Outcome, Player, Position,
+};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    io::{self, Read, Write},
+};
+
+const FILE_MAGIC: &[u8; 4] = b"OBK1";
+/// Black discs, white discs, player to move and three counters.
+const ENTRY_BYTES: usize = 8 + 8 + 1 + 3 * 4;
+
+/// Results of all book games which went through a position.
+#[must_use]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BookEntry {
+    pub black_wins: u32,
+    pub draws: u32,
+    pub white_wins: u32,
+}
+
+impl BookEntry {
+    #[must_use]
+    pub const fn games(&self) -> u32 {
+        self.black_wins + self.draws + self.white_wins
+    }
+
+    #[must_use]
+    pub const fn wins(&self, player: Player) -> u32 {
+        match player {
+            Player::Black => self.black_wins,
+            Player::White => self.white_wins,
+        }
+    }
+
+    #[must_use]
+    pub const fn losses(&self, player: Player) -> u32 {
+        self.wins(player.opponent())
+    }
+
+    /// Average result for the player, where a win is worth 1 and a draw 0.5.
+    #[must_use]
+    pub fn score(&self, player: Player) -> f64 {
+        (self.wins(player) as f64 + 0.5 * self.draws as f64) / self.games() as f64
+    }
+
+    fn add(&mut self, outcome: &Outcome) {
+        match outcome {
+            Outcome::Winner(Player::Black) => self.black_wins += 1,
+            Outcome::Winner(Player::White) => self.white_wins += 1,
+            Outcome::Draw => self.draws += 1,
+        }
+    }
+
+    fn merge(&mut self, other: &BookEntry) {
+        self.black_wins += other.black_wins;
+        self.draws += other.draws;
+        self.white_wins += other.white_wins;
+    }
+}
+
+/// Plays the best scoring move seen in the book games, then falls back to another strategy.
+#[must_use]
+pub struct OpeningBook {
+    entries: HashMap<GameState, BookEntry, ZobristBuildHasher>,
+    min_games: u32,
+    fallback: Box<dyn Strategy>,
+}
+
+impl OpeningBook {
+    pub fn new(fallback: Box<dyn Strategy>) -> Self {
+        Self {
+            entries: HashMap::default(),
+            min_games: 1,
+            fallback,
+        }
+    }
+
+    /// Only plays moves leading to positions, which appeared in at least the given number of games.
+    pub fn with_min_games(mut self, min_games: u32) -> Self {
+        self.min_games = min_games.max(1);
+        self
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[must_use]
+    pub fn entry(&self, gs: &GameState) -> Option<&BookEntry> {
+        self.entries.get(gs)
+    }
+
+    /// Adds every position of the game, unfinished games are skipped.
+    pub fn add_game(&mut self, record: &GameRecord) {
+        let Some(outcome) = record.end().outcome() else {
+            return;
+        };
+
+        let mut gs = record.start().clone();
+        self.entries.entry(gs.clone()).or_default().add(&outcome);
+        for &position in record.moves() {
+            gs = gs.make_move(position);
+            self.entries.entry(gs.clone()).or_default().add(&outcome);
+        }
+    }
+
+    /// Adds games given as transcripts played from the standard Othello position.
+    pub fn add_transcripts<'a>(
+        &mut self,
+        transcripts: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), RecordError> {
+        for transcript in transcripts {
+            let record = GameRecord::from_transcript(GameState::othello_initial(), transcript)?;
+            self.add_game(&record);
+        }
+        Ok(())
+    }
+
+    /// Returns the move with the best results for the player to move, if any is in the book.
+    #[must_use]
+    pub fn book_move(&self, gs: &GameState) -> Option<Position> {
+        let player = gs.turn();
+        gs.moves()
+            .into_iter()
+            .filter_map(|position| {
+                let entry = self.entries.get(&gs.make_move(position))?;
+                (entry.games() >= self.min_games).then_some((position, entry))
+            })
+            .max_by(|(_, a), (_, b)| {
+                a.score(player)
+                    .total_cmp(&b.score(player))
+                    .then(a.games().cmp(&b.games()))
+            })
+            .map(|(position, _)| position)
+    }
+
+    /// Writes all entries in a binary format, positions are written in an unspecified order.
+    pub fn save(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(FILE_MAGIC)?;
+        writer.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        for (gs, entry) in &self.entries {
+            writer.write_all(&gs.bb_of(Player::Black).to_le_bytes())?;
+            writer.write_all(&gs.bb_of(Player::White).to_le_bytes())?;
+            writer.write_all(&[gs.turn() as u8])?;
+            for counter in [entry.black_wins, entry.draws, entry.white_wins] {
+                writer.write_all(&counter.to_le_bytes())?;
+            }
+        }
+        writer.flush()
+    }
+
+    /// Reads entries written by `save`, adding them to the ones already in the book.
+    pub fn load(&mut self, mut reader: impl Read) -> io::Result<()> {
+        let invalid = |reason: &str| io::Error::new(io::ErrorKind::InvalidData, reason);
+
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != FILE_MAGIC {
+            return Err(invalid("not an opening book file"));
+        }
+        let count = u32::from_le_bytes(header[4..].try_into().unwrap());
+
+        let mut bytes = [0; ENTRY_BYTES];
+        for _ in 0..count {
+            reader.read_exact(&mut bytes)?;
+            let u64_at = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+            let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+
+            let turn = match bytes[16] {
+                t if t == Player::Black as u8 => Player::Black,
+                t if t == Player::White as u8 => Player::White,
+                _ => return Err(invalid("invalid player to move")),
+            };
+            let gs = GameState::from_discs(u64_at(0), u64_at(8), Some(turn))
+                .map_err(|error| invalid(&error.to_string()))?;
+            let entry = BookEntry {
+                black_wins: u32_at(17),
+                draws: u32_at(21),
+                white_wins: u32_at(25),
+            };
+            self.entries.entry(gs).or_default().merge(&entry);
+        }
+        Ok(())
+    }
+}
+
+impl Display for OpeningBook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Book({})", self.fallback)
+    }
+}
+
+impl Strategy for OpeningBook {
+    fn decide(&self, gs: &GameState) -> Position {
+        self.book_move(gs)
+            .unwrap_or_else(|| self.fallback.decide(gs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ai::FirstMove, game::p};
+
+    const TRANSCRIPTS: [&str; 3] = [
+        // Shortest possible game, won by Black
+        "E6F4E3F6G5D6E7F5C5",
+        // Hassan 3 – 17 Verstuyft, won by White
+        "D3E3F4G3F3C5H3F2C4C3E2E1B3H4H5A3",
+        // Chitra 32 - 32 Fu, draw
+        "F5D6C3D3C4F4F6G5E6D7E3G6C7C5C6B6G3B5B4F2A5A6H5C2E8F3E2A3A4B3G4E7D2F7D8D1H6C8F8E1B8H4B7H7G7B2A1A2B1C1F1H8G8A8A7G2H2H3G1H1",
+    ];
+
+    fn book() -> OpeningBook {
+        let mut book = OpeningBook::new(Box::new(FirstMove));
+        book.add_transcripts(TRANSCRIPTS).unwrap();
+        book
+    }
+
+    #[test]
+    fn entries_count_results_of_all_games() {
+        let book = book();
+        let entry = book.entry(&GameState::othello_initial()).unwrap();
+        assert_eq!(entry.games(), 3);
+        assert_eq!(entry.wins(Player::Black), 1);
+        assert_eq!(entry.losses(Player::Black), 1);
+        assert_eq!(entry.draws, 1);
+    }
+
+    #[test]
+    fn book_plays_the_best_scoring_move() {
+        let book = book();
+        let gs = GameState::othello_initial();
+        assert_eq!(book.decide(&gs), p("E6"));
+        let gs = gs.make_move(p("D3"));
+        assert_eq!(book.decide(&gs), p("E3"));
+    }
+
+    #[test]
+    fn book_falls_back_outside_of_known_positions() {
+        let book = book();
+        let gs = GameState::othello_initial().make_move(p("C4"));
+        assert_eq!(book.book_move(&gs), None);
+        assert_eq!(book.decide(&gs), FirstMove.decide(&gs));
+    }
+
+    #[test]
+    fn min_games_limits_played_moves() {
+        let book = book().with_min_games(2);
+        assert_eq!(book.book_move(&GameState::othello_initial()), None);
+    }
+
+    #[test]
+    fn saved_book_can_be_loaded() {
+        let book = book();
+        let mut bytes = Vec::new();
+        book.save(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 8 + book.len() * ENTRY_BYTES);
+
+        let mut loaded = OpeningBook::new(Box::new(FirstMove));
+        loaded.load(bytes.as_slice()).unwrap();
+        assert_eq!(loaded.entries, book.entries);
+
+        assert!(loaded.load(&b"nonsense"[..]).is_err());
+    }
+}
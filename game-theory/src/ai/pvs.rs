@@ -0,0 +1,253 @@
+use super::{
+    alpha_beta::SearchContext,
+    heuristics::MAX_PLAYER,
+    transposition::{Bound, TableEntry, DEFAULT_TABLE_SIZE},
+    Evaluator, MoveOrdering, SearchResult, Strategy, TreeVisitingStrategy,
+};
+use crate::game::{GameState, Player, Position};
+use std::{
+    fmt::{self, Display},
+    sync::{
+        atomic::{self, AtomicU32},
+        Arc,
+    },
+    time::Instant,
+};
+
+/// Negamax search with Principal Variation Search and optional aspiration windows.
+///
+/// Scores are kept from the perspective of the player to move, so unlike in `AlphaBeta`
+/// both players share the same code path.
+#[must_use]
+pub struct Pvs {
+    evaluator: Arc<dyn Evaluator>,
+    max_depth: u32,
+    table_size: usize,
+    ordering: MoveOrdering,
+    aspiration: Option<f64>,
+    fast_wins: bool,
+    visited: AtomicU32,
+}
+
+#[must_use]
+const fn sign(player: Player) -> f64 {
+    if matches!(player, MAX_PLAYER) {
+        1.
+    } else {
+        -1.
+    }
+}
+
+impl Pvs {
+    pub fn new(evaluator: impl Into<Arc<dyn Evaluator>>, max_depth: u32) -> Self {
+        Self {
+            evaluator: evaluator.into(),
+            max_depth,
+            table_size: DEFAULT_TABLE_SIZE,
+            ordering: MoveOrdering::ALL,
+            aspiration: None,
+            fast_wins: false,
+            visited: AtomicU32::new(0),
+        }
+    }
+
+    /// Sets the number of positions remembered in a single search, 0 disables the table.
+    pub const fn with_table_size(mut self, table_size: usize) -> Self {
+        self.table_size = table_size;
+        self
+    }
+
+    pub const fn with_ordering(mut self, ordering: MoveOrdering) -> Self {
+        self.ordering = ordering;
+        self
+    }
+
+    /// Searches the root with a window of the given half-width around the score of a shallower search.
+    pub const fn with_aspiration(mut self, half_width: f64) -> Self {
+        self.aspiration = Some(half_width);
+        self
+    }
+
+    /// Prefers faster wins and slower losses over the final disc difference.
+    pub const fn with_fast_wins(mut self, fast_wins: bool) -> Self {
+        self.fast_wins = fast_wins;
+        self
+    }
+
+    /// Searches the root, returning the score from the perspective of the player to move.
+    fn search(&self, gs: &GameState, depth: u32, ctx: &mut SearchContext) -> (f64, Position) {
+        ctx.start(gs);
+        let full_window = (f64::NEG_INFINITY, f64::INFINITY);
+
+        let window = match self.aspiration {
+            Some(half_width) if depth > 1 => {
+                let (guess, _) = self.negamax(gs, depth - 1, full_window.0, full_window.1, ctx);
+                (guess - half_width, guess + half_width)
+            }
+            _ => full_window,
+        };
+
+        let (mut score, mut position) = self.negamax(gs, depth, window.0, window.1, ctx);
+        if score <= window.0 || score >= window.1 {
+            // Result outside of the aspiration window is only a bound, search again
+            (score, position) = self.negamax(gs, depth, full_window.0, full_window.1, ctx);
+        }
+        (score, position.unwrap())
+    }
+
+    /// Searches a child position, taking care of the opponent passing.
+    #[must_use]
+    fn child(
+        &self,
+        gs: &GameState,
+        child: &GameState,
+        depth: u32,
+        alpha: f64,
+        beta: f64,
+        ctx: &mut SearchContext,
+    ) -> f64 {
+        if child.turn() == gs.turn() {
+            self.negamax(child, depth, alpha, beta, ctx).0
+        } else {
+            -self.negamax(child, depth, -beta, -alpha, ctx).0
+        }
+    }
+
+    #[must_use]
+    fn negamax(
+        &self,
+        gs: &GameState,
+        depth: u32,
+        mut alpha: f64,
+        mut beta: f64,
+        ctx: &mut SearchContext,
+    ) -> (f64, Option<Position>) {
+        self.visited.fetch_add(1, atomic::Ordering::Relaxed);
+
+        if gs.outcome().is_some() {
+            return (sign(gs.turn()) * gs.final_score(self.fast_wins), None);
+        }
+
+        if depth == 0 {
+            return (sign(gs.turn()) * self.evaluator.evaluate(gs), None);
+        }
+
+        let mut hash_move = None;
+        if let Some(entry) = ctx.table.probe(gs) {
+            if entry.depth >= depth {
+                match entry.bound {
+                    Bound::Exact => return (entry.score, entry.best_move),
+                    Bound::Lower => alpha = alpha.max(entry.score),
+                    Bound::Upper => beta = beta.min(entry.score),
+                }
+                if beta <= alpha {
+                    return (entry.score, entry.best_move);
+                }
+            }
+            hash_move = entry.best_move;
+        }
+        let (alpha_orig, beta_orig) = (alpha, beta);
+
+        let ply = ctx.ply(gs);
+        let moves = ctx.ordering.order(gs, ply, hash_move);
+        let mut best_pos = moves[0];
+        let mut best_score = f64::NEG_INFINITY;
+        for (i, position) in moves.into_iter().enumerate() {
+            let child = gs.make_move(position);
+            let score = if i == 0 {
+                self.child(gs, &child, depth - 1, alpha, beta, ctx)
+            } else {
+                // Zero-window search only proves whether the move is better than the current best
+                let score = self.child(gs, &child, depth - 1, alpha, alpha.next_up(), ctx);
+                if score > alpha && score < beta {
+                    self.child(gs, &child, depth - 1, alpha, beta, ctx)
+                } else {
+                    score
+                }
+            };
+
+            if score > best_score {
+                best_score = score;
+                best_pos = position;
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                ctx.ordering.record_cutoff(ply, position, depth);
+                break;
+            }
+        }
+
+        ctx.table.store(
+            gs,
+            TableEntry::from_window(depth, best_score, Some(best_pos), alpha_orig, beta_orig),
+        );
+        (best_score, Some(best_pos))
+    }
+}
+
+impl Display for Pvs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PVS({}, {})", self.evaluator, self.max_depth)
+    }
+}
+
+impl Strategy for Pvs {
+    fn decide(&self, gs: &GameState) -> Position {
+        self.analyze(gs).position
+    }
+}
+
+impl TreeVisitingStrategy for Pvs {
+    fn visited(&self) -> u32 {
+        self.visited.load(atomic::Ordering::Relaxed)
+    }
+
+    fn analyze(&self, gs: &GameState) -> SearchResult {
+        let start = Instant::now();
+        let visited_before = self.visited();
+        let mut ctx = SearchContext::new(self.table_size, self.ordering, None);
+        let (score, position) = self.search(gs, self.max_depth, &mut ctx);
+        SearchResult {
+            position,
+            score: sign(gs.turn()) * score,
+            principal_variation: ctx.principal_variation(gs, position, self.max_depth),
+            depth: self.max_depth,
+            nodes: self.visited() - visited_before,
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::{AlphaBeta, Heuristic};
+    use quickcheck_macros::quickcheck;
+
+    const DEPTH: u32 = 3;
+
+    #[quickcheck]
+    fn pvs_returns_the_same_move_as_alpha_beta(heuristic: Heuristic, gs: GameState) -> bool {
+        let pvs = Pvs::new(heuristic.clone(), DEPTH)
+            .with_ordering(MoveOrdering::NONE)
+            .with_table_size(0);
+        let alpha_beta = AlphaBeta::new(heuristic, DEPTH)
+            .with_ordering(MoveOrdering::NONE)
+            .with_table_size(0);
+        gs.outcome().is_some() || pvs.decide(&gs) == alpha_beta.decide(&gs)
+    }
+
+    #[quickcheck]
+    fn pvs_returns_the_same_score_as_alpha_beta(heuristic: Heuristic, gs: GameState) -> bool {
+        let pvs = Pvs::new(heuristic.clone(), DEPTH);
+        let alpha_beta = AlphaBeta::new(heuristic, DEPTH);
+        gs.outcome().is_some() || pvs.analyze(&gs).score == alpha_beta.analyze(&gs).score
+    }
+
+    #[quickcheck]
+    fn aspiration_windows_keep_the_score(heuristic: Heuristic, gs: GameState) -> bool {
+        let plain = Pvs::new(heuristic.clone(), DEPTH);
+        let aspiration = Pvs::new(heuristic, DEPTH).with_aspiration(0.05);
+        gs.outcome().is_some() || plain.analyze(&gs).score == aspiration.analyze(&gs).score
+    }
+}
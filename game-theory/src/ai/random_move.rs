@@ -1,10 +1,37 @@
 use super::strategy::Strategy;
-use crate::game::{GameState, Position};
-use rand::{seq::SliceRandom, thread_rng};
+use crate::{
+    game::{GameState, Position},
+    utils::rng::StrategyRng,
+};
+use rand::{seq::SliceRandom, Rng};
 use std::fmt::{self, Display};
 
+/// Plays uniformly random moves, drawn from the generator of the current thread unless seeded.
 #[derive(Default)]
-pub struct RandomMove;
+pub struct RandomMove {
+    rng: StrategyRng,
+}
+
+impl RandomMove {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            rng: StrategyRng::new(),
+        }
+    }
+
+    /// Draws the moves from its own generator with the given seed.
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StrategyRng::seeded(seed);
+        self
+    }
+
+    /// Picks a random move with the given generator.
+    pub fn choose(gs: &GameState, rng: &mut impl Rng) -> Position {
+        *gs.moves().choose(rng).unwrap()
+    }
+}
 
 impl Display for RandomMove {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -14,7 +41,7 @@ impl Display for RandomMove {
 
 impl Strategy for RandomMove {
     fn decide(&self, gs: &GameState) -> Position {
-        *gs.moves().choose(&mut thread_rng()).unwrap()
+        self.rng.with(|rng| Self::choose(gs, rng))
     }
 }
 
@@ -25,6 +52,21 @@ mod tests {
 
     #[quickcheck]
     fn random_move_always_returns_a_valid_move(gs: GameState) -> bool {
-        gs.outcome().is_some() || gs.moves().contains(&RandomMove::default().decide(&gs))
+        gs.outcome().is_some() || gs.moves().contains(&RandomMove::new().decide(&gs))
+    }
+
+    #[quickcheck]
+    fn same_seed_gives_the_same_moves(seed: u64) -> bool {
+        let play = |strategy: RandomMove| {
+            let mut gs = GameState::othello_initial();
+            let mut moves = Vec::new();
+            while gs.outcome().is_none() {
+                let position = strategy.decide(&gs);
+                moves.push(position);
+                gs = gs.make_move(position);
+            }
+            moves
+        };
+        play(RandomMove::new().with_seed(seed)) == play(RandomMove::new().with_seed(seed))
     }
 }
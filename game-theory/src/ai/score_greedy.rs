@@ -1,13 +1,34 @@
 use super::strategy::Strategy;
-use crate::game::{GameState, Position};
-use rand::{seq::SliceRandom, thread_rng};
+use crate::{
+    game::{GameState, Position},
+    utils::rng::StrategyRng,
+};
+use rand::seq::SliceRandom;
 use std::{
     cmp::Ordering,
     fmt::{self, Display},
 };
 
 #[derive(Default)]
-pub struct ScoreGreedy;
+pub struct ScoreGreedy {
+    rng: StrategyRng,
+}
+
+impl ScoreGreedy {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            rng: StrategyRng::new(),
+        }
+    }
+
+    /// Breaks ties with its own generator with the given seed.
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StrategyRng::seeded(seed);
+        self
+    }
+}
 
 impl Display for ScoreGreedy {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -32,6 +53,6 @@ impl Strategy for ScoreGreedy {
                 }
             };
         }
-        *best_moves.choose(&mut thread_rng()).unwrap()
+        self.rng.with(|rng| *best_moves.choose(rng).unwrap())
     }
 }
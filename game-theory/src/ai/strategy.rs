@@ -1,5 +1,8 @@
 use crate::game::{GameState, Position};
-use std::fmt::Display;
+use std::{
+    fmt::{self, Display},
+    time::{Duration, Instant},
+};
 
 pub trait Strategy: Display + Sync {
     fn decide(&self, gs: &GameState) -> Position;
@@ -8,4 +11,99 @@ pub trait Strategy: Display + Sync {
 pub trait TreeVisitingStrategy: Strategy {
     #[must_use]
     fn visited(&self) -> u32;
+
+    /// Decides on a move, also returning the information gathered during the search.
+    ///
+    /// By default, only the move, visited nodes and time are known, with no score
+    /// or principal variation.
+    fn analyze(&self, gs: &GameState) -> SearchResult {
+        let start = Instant::now();
+        let visited_before = self.visited();
+        let position = self.decide(gs);
+        SearchResult {
+            position,
+            score: 0.,
+            principal_variation: Vec::new(),
+            depth: 0,
+            nodes: self.visited() - visited_before,
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+#[must_use]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResult {
+    pub position: Position,
+    /// Evaluation of the position, positive values favor Black.
+    pub score: f64,
+    /// Expected line of play, starting with the chosen move.
+    pub principal_variation: Vec<Position>,
+    pub depth: u32,
+    pub nodes: u32,
+    pub elapsed: Duration,
+}
+
+impl Display for SearchResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "move {} | score {:+.3} | depth {} | nodes {:.1e} | {} ms | pv",
+            self.position,
+            self.score,
+            self.depth,
+            self.nodes,
+            self.elapsed.as_millis()
+        )?;
+        for position in &self.principal_variation {
+            write!(f, " {position}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Plays the first move, visiting every position it could move to.
+    #[derive(Default)]
+    struct FirstMove {
+        visited: AtomicU32,
+    }
+
+    impl Display for FirstMove {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "First")
+        }
+    }
+
+    impl Strategy for FirstMove {
+        fn decide(&self, gs: &GameState) -> Position {
+            let moves = gs.moves();
+            self.visited
+                .fetch_add(moves.len() as u32, Ordering::Relaxed);
+            moves[0]
+        }
+    }
+
+    impl TreeVisitingStrategy for FirstMove {
+        fn visited(&self) -> u32 {
+            self.visited.load(Ordering::Relaxed)
+        }
+    }
+
+    #[test]
+    fn analysis_defaults_to_the_decided_move() {
+        let strategy = FirstMove::default();
+        let gs = GameState::othello_initial();
+        let result = strategy.analyze(&gs);
+        assert_eq!(result.position, gs.moves()[0]);
+        assert_eq!(result.nodes, 4);
+        assert!(result.principal_variation.is_empty());
+        // Only the nodes of this search are counted
+        let gs = gs.make_move(result.position);
+        assert_eq!(strategy.analyze(&gs).nodes, 3);
+    }
 }
@@ -0,0 +1,145 @@
+use crate::game::{zobrist::ZobristBuildHasher, GameState, Position};
+use std::collections::HashMap;
+
+pub const DEFAULT_TABLE_SIZE: usize = 1 << 16;
+
+#[must_use]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    /// Score is the exact minimax value of the position.
+    Exact,
+    /// Search failed high, the real value is at least the score.
+    Lower,
+    /// Search failed low, the real value is at most the score.
+    Upper,
+}
+
+#[must_use]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TableEntry {
+    pub depth: u32,
+    pub bound: Bound,
+    pub score: f64,
+    pub best_move: Option<Position>,
+}
+
+impl TableEntry {
+    /// Classifies a fail-soft search result against the window it was searched with.
+    pub fn from_window(
+        depth: u32,
+        score: f64,
+        best_move: Option<Position>,
+        alpha: f64,
+        beta: f64,
+    ) -> Self {
+        let bound = if score <= alpha {
+            Bound::Upper
+        } else if score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        Self {
+            depth,
+            bound,
+            score,
+            best_move,
+        }
+    }
+}
+
+/// Bounded cache of search results, keyed by the whole game state.
+///
+/// Once `capacity` positions are stored, new positions are no longer inserted,
+/// but already stored ones can still be replaced by results of deeper searches.
+#[must_use]
+pub struct TranspositionTable {
+    entries: HashMap<GameState, TableEntry, ZobristBuildHasher>,
+    capacity: usize,
+}
+
+impl TranspositionTable {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::default(),
+            capacity,
+        }
+    }
+
+    #[must_use]
+    pub fn probe(&self, gs: &GameState) -> Option<&TableEntry> {
+        self.entries.get(gs)
+    }
+
+    pub fn store(&mut self, gs: &GameState, entry: TableEntry) {
+        let has_space = self.entries.len() < self.capacity;
+        match self.entries.get_mut(gs) {
+            Some(existing) if existing.depth <= entry.depth => *existing = entry,
+            Some(_) => (),
+            None if has_space => {
+                self.entries.insert(gs.clone(), entry);
+            }
+            None => (),
+        }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::p;
+
+    fn entry(depth: u32, score: f64) -> TableEntry {
+        TableEntry {
+            depth,
+            bound: Bound::Exact,
+            score,
+            best_move: Some(p("D3")),
+        }
+    }
+
+    #[test]
+    fn from_window_classifies_bounds() {
+        let bound = |score| TableEntry::from_window(1, score, None, -0.5, 0.5).bound;
+        assert_eq!(bound(-0.5), Bound::Upper);
+        assert_eq!(bound(0.), Bound::Exact);
+        assert_eq!(bound(0.7), Bound::Lower);
+    }
+
+    #[test]
+    fn deeper_entries_replace_shallower_ones() {
+        let gs = GameState::othello_initial();
+        let mut table = TranspositionTable::new(DEFAULT_TABLE_SIZE);
+        table.store(&gs, entry(2, 0.1));
+        table.store(&gs, entry(1, 0.2));
+        assert_eq!(table.probe(&gs), Some(&entry(2, 0.1)));
+        table.store(&gs, entry(3, 0.3));
+        assert_eq!(table.probe(&gs), Some(&entry(3, 0.3)));
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn table_does_not_grow_past_capacity() {
+        let first = GameState::othello_initial();
+        let second = first.make_move(p("D3"));
+        let mut table = TranspositionTable::new(1);
+        table.store(&first, entry(1, 0.));
+        table.store(&second, entry(5, 0.));
+        assert_eq!(table.len(), 1);
+        assert!(table.probe(&second).is_none());
+    }
+}
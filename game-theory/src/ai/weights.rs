@@ -1,7 +1,129 @@
-use crate::game::BOARD_SQUARES;
+use super::heuristics::HEURISTIC_BOUND;
+use crate::game::{BOARD_SIDE, BOARD_SQUARES};
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
 
 pub type WeightMatrix = [i32; BOARD_SQUARES];
 
+/// Number of values in the compact form, one for every square of the A1-D1-D4 triangle.
+pub const COMPACT_WEIGHT_LEN: usize = 10;
+
+/// Reason why a weight matrix could not be parsed.
+#[must_use]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WeightsError {
+    /// Neither the full nor the compact number of values was found.
+    WrongCount(usize),
+    /// Row of a full matrix doesn't have 8 values, rows are numbered from 0.
+    WrongRowLength { row: usize, found: usize },
+    /// Value isn't an integer.
+    InvalidValue(String),
+    /// Without a positive weight, evaluations can't be normalized.
+    NoPositiveWeight,
+    /// Negative weights outweigh the positive ones so much that evaluations could exceed
+    /// `HEURISTIC_BOUND`, the sums of both are given.
+    Unbounded { positive: i64, negative: i64 },
+}
+
+impl Display for WeightsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeightsError::WrongCount(found) => write!(
+                f,
+                "expected {BOARD_SQUARES} values in {BOARD_SIDE} rows \
+                 or {COMPACT_WEIGHT_LEN} symmetric values, found {found}"
+            ),
+            WeightsError::WrongRowLength { row, found } => write!(
+                f,
+                "expected {BOARD_SIDE} values in row {}, found {found}",
+                row + 1
+            ),
+            WeightsError::InvalidValue(value) => write!(f, "'{value}' isn't an integer"),
+            WeightsError::NoPositiveWeight => write!(f, "at least one weight must be positive"),
+            WeightsError::Unbounded { positive, negative } => write!(
+                f,
+                "negative weights sum to {negative}, which is more than \
+                 {HEURISTIC_BOUND} times the sum of positive weights {positive}"
+            ),
+        }
+    }
+}
+
+impl Error for WeightsError {}
+
+/// Parses a full matrix with a row per line, or the compact form in any layout.
+///
+/// Values are separated with whitespace or commas, and `#` starts a comment.
+/// The compact form gives the squares A1, B1, C1, D1, B2, C2, D2, C3, D3 and D4,
+/// which are mirrored to the rest of the board. Negative weights can sum to at most
+/// `HEURISTIC_BOUND` times the positive ones, so that evaluations stay within the bound.
+pub fn parse_weights(text: &str) -> Result<WeightMatrix, WeightsError> {
+    let rows = text
+        .lines()
+        .map(|line| {
+            line.split('#')
+                .next()
+                .unwrap()
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|value| !value.is_empty())
+                .map(|value| {
+                    value
+                        .parse::<i32>()
+                        .map_err(|_| WeightsError::InvalidValue(value.to_string()))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .filter(|row| !matches!(row, Ok(values) if values.is_empty()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let values = rows.concat();
+    let weights = match values.len() {
+        COMPACT_WEIGHT_LEN => expand_compact(values.try_into().unwrap()),
+        BOARD_SQUARES => {
+            if let Some((row, values)) = rows
+                .iter()
+                .enumerate()
+                .find(|(_, values)| values.len() != BOARD_SIDE)
+            {
+                let found = values.len();
+                return Err(WeightsError::WrongRowLength { row, found });
+            }
+            values.try_into().unwrap()
+        }
+        found => return Err(WeightsError::WrongCount(found)),
+    };
+
+    if weights.iter().all(|&weight| weight <= 0) {
+        return Err(WeightsError::NoPositiveWeight);
+    }
+    // Evaluations are normalized by the positive weights, so the negative ones bound them
+    let positive = weights.iter().map(|&w| i64::from(w.max(0))).sum::<i64>();
+    let negative = weights.iter().map(|&w| i64::from(w.min(0))).sum::<i64>();
+    if -negative as f64 > HEURISTIC_BOUND * positive as f64 {
+        return Err(WeightsError::Unbounded { positive, negative });
+    }
+    Ok(weights)
+}
+
+/// Mirrors the values of the A1-D1-D4 triangle to all 8 symmetric squares.
+#[must_use]
+pub fn expand_compact(compact: [i32; COMPACT_WEIGHT_LEN]) -> WeightMatrix {
+    let half = BOARD_SIDE / 2;
+    let mut weights = [0; BOARD_SQUARES];
+    for (i, weight) in weights.iter_mut().enumerate() {
+        let (row, col) = (i / BOARD_SIDE, i % BOARD_SIDE);
+        let row = row.min(BOARD_SIDE - 1 - row);
+        let col = col.min(BOARD_SIDE - 1 - col);
+        let (near, far) = (row.min(col), row.max(col));
+        // Rows of the triangle get shorter by one square each
+        let index = near * half - near * (near.saturating_sub(1)) / 2 + (far - near);
+        *weight = compact[index];
+    }
+    weights
+}
+
 #[rustfmt::skip]
 pub const WEIGHTS_MAGGS: WeightMatrix = [
      64, -30,  10,   5,   5,  10, -30,  64,
@@ -37,3 +159,54 @@ pub const WEIGHTS_KORMAN: WeightMatrix = [
     -3, -7, -4,  1,  1, -4, -7, -3,
     20, -3, 11,  8,  8, 11, -3, 20,
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAGGS_COMPACT: [i32; COMPACT_WEIGHT_LEN] = [64, -30, 10, 5, -40, 2, 2, 5, 1, 1];
+
+    #[test]
+    fn compact_weights_are_mirrored() {
+        assert_eq!(expand_compact(MAGGS_COMPACT), WEIGHTS_MAGGS);
+        let korman = [20, -3, 11, 8, -7, -4, 1, 2, 2, -3];
+        assert_eq!(expand_compact(korman), WEIGHTS_KORMAN);
+    }
+
+    #[test]
+    fn full_and_compact_matrices_are_parsed() {
+        let full = WEIGHTS_VAISHU_MUTHU
+            .chunks(BOARD_SIDE)
+            .map(|row| row.iter().map(i32::to_string).collect::<Vec<_>>().join(" "))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(parse_weights(&full), Ok(WEIGHTS_VAISHU_MUTHU));
+        let compact = "# Maggs 1979\n64, -30, 10, 5\n-40, 2, 2\n5, 1\n1\n";
+        assert_eq!(parse_weights(compact), Ok(WEIGHTS_MAGGS));
+    }
+
+    #[test]
+    fn invalid_matrices_are_rejected() {
+        assert_eq!(parse_weights("1 2 3"), Err(WeightsError::WrongCount(3)));
+        assert_eq!(
+            parse_weights("1 2 3 4 5 6 7 8 9 x"),
+            Err(WeightsError::InvalidValue("x".to_string()))
+        );
+        assert_eq!(
+            parse_weights("0 0 0 0 0 0 0 0 0 -1"),
+            Err(WeightsError::NoPositiveWeight)
+        );
+        assert_eq!(
+            parse_weights("1 -50 0 0 0 0 0 0 0 0"),
+            Err(WeightsError::Unbounded {
+                positive: 4,
+                negative: -400
+            })
+        );
+        let shifted = format!("{}\n1 1 1 1 1 1 1 1 1\n{}", "1 ".repeat(7), "1 ".repeat(48));
+        assert_eq!(
+            parse_weights(&shifted),
+            Err(WeightsError::WrongRowLength { row: 0, found: 7 })
+        );
+    }
+}
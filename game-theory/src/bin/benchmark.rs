@@ -1,25 +1,92 @@
+use clap::Parser;
 use game_theory::{
     ai::{RandomMove, Strategy},
-    game::GameState,
+    game::{
+        bitboard::{self as bb, dumb7fill, kogge_stone, Bitboard},
+        GameState, Position,
+    },
+    utils::rng,
 };
-use std::time::{Duration, Instant};
+use std::{hint::black_box, time::Instant};
+
+type Fills = (
+    fn(Bitboard, Bitboard) -> Bitboard,
+    fn(Bitboard, Bitboard, Bitboard) -> Bitboard,
+);
+
+#[derive(Parser)]
+struct Args {
+    /// Seed for all random choices, so that runs can be repeated
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+/// Times the given closure on all samples at once, returns the mean duration in nanoseconds.
+///
+/// Calls take only a few nanoseconds, so timing each of them would mostly measure the timer.
+fn time_per_call(samples: &[(GameState, Position)], f: impl Fn(&GameState, Position)) -> f64 {
+    let start = Instant::now();
+    for (gs, position) in samples {
+        f(black_box(gs), black_box(*position));
+    }
+    start.elapsed().as_nanos() as f64 / samples.len() as f64
+}
+
+/// Same steps as `bitboard::make_move`, with the given fills.
+fn make_move((attack_fill, all_flipped): Fills, gs: &GameState, position: Position) -> Bitboard {
+    let current = gs.bb_of(gs.turn());
+    let opponent = gs.bb_of(gs.turn().opponent());
+    let position = bb::from_pos(position);
+    assert!(
+        attack_fill(current, opponent) & position != 0,
+        "Invalid move!"
+    );
+    current | position | all_flipped(position, current, opponent)
+}
 
 fn main() {
-    let mut total = Duration::ZERO;
-    let mut tries = 0;
-    let strategy = RandomMove::default();
+    let args = Args::parse();
+    if let Some(seed) = args.seed {
+        rng::seed(seed);
+    }
+    let strategy = RandomMove::new();
+    let mut samples = Vec::new();
     for _ in 0..1000 {
         for n in 1..=60 {
             let gs = GameState::random_state_between_inc(n, n);
-            if gs.outcome().is_some() {
+            if gs.outcome().is_some() || gs.occupied_bb().count_ones() < 4 {
                 continue;
             }
             let position = strategy.decide(&gs);
-            let before = Instant::now();
-            let _ = gs.make_move(position);
-            total += before.elapsed();
-            tries += 1;
+            samples.push((gs, position));
         }
     }
-    println!("{} ns", total.as_nanos() / tries);
+
+    let implementations: [(&str, Fills); 2] = [
+        (
+            "dumb7fill",
+            (dumb7fill::attack_fill, dumb7fill::all_flipped),
+        ),
+        (
+            "Kogge-Stone",
+            (kogge_stone::attack_fill, kogge_stone::all_flipped),
+        ),
+    ];
+    for (name, fills) in implementations {
+        let (attack_fill, _) = fills;
+        let valid_moves = time_per_call(&samples, |gs, _| {
+            let current = gs.bb_of(gs.turn());
+            let opponent = gs.bb_of(gs.turn().opponent());
+            black_box(attack_fill(black_box(current), black_box(opponent)));
+        });
+        let make_move = time_per_call(&samples, |gs, position| {
+            black_box(make_move(fills, black_box(gs), black_box(position)));
+        });
+        println!("{name:>12}: valid_moves {valid_moves:.1} ns, make_move {make_move:.1} ns");
+    }
+
+    let make_move = time_per_call(&samples, |gs, position| {
+        let _ = black_box(black_box(gs).make_move(black_box(position)));
+    });
+    println!("GameState::make_move: {make_move:.1} ns");
 }
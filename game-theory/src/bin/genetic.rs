@@ -1,3 +1,4 @@
+use clap::Parser;
 use game_theory::{
     ai::{
         heuristics::{linear_hash, LINEAR_WEIGHT_LEN},
@@ -6,10 +7,11 @@ use game_theory::{
     game::{Outcome, Player},
     utils::{
         elo::{elo_update, INITIAL_ELO},
+        rng,
         tournament::run_tournament,
     },
 };
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use std::fmt::Write;
 use std::{cmp::Ordering, time::Duration};
 
@@ -23,8 +25,16 @@ const MUTATION_MAGNITUDE: f64 = 0.1;
 
 type Chromosome = [f64; LINEAR_WEIGHT_LEN];
 
-fn random_chromosome() -> Chromosome {
-    [0.; LINEAR_WEIGHT_LEN].map(|_| thread_rng().gen_range(-1. ..=1.))
+#[derive(Parser)]
+struct Args {
+    /// Seed for all random choices, the games of a generation still differ between runs
+    /// as they are played until a time limit
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+fn random_chromosome(rng: &mut impl Rng) -> Chromosome {
+    [0.; LINEAR_WEIGHT_LEN].map(|_| rng.gen_range(-1. ..=1.))
 }
 
 fn chromosome_string(chromosome: &Chromosome) -> String {
@@ -38,9 +48,15 @@ fn chromosome_string(chromosome: &Chromosome) -> String {
 }
 
 fn main() {
-    let mut population = (0..POPULATION_SIZE)
-        .map(|_| random_chromosome())
-        .collect::<Vec<_>>();
+    let args = Args::parse();
+    if let Some(seed) = args.seed {
+        rng::seed(seed);
+    }
+    let mut population = rng::with_rng(|rng| {
+        (0..POPULATION_SIZE)
+            .map(|_| random_chromosome(rng))
+            .collect::<Vec<_>>()
+    });
 
     for generation in 1.. {
         println!("Training generation #{generation}...");
@@ -58,11 +74,11 @@ fn main() {
         let rx = run_tournament(POPULATION_SIZE, TIME_PER_GEN, |i| &strategies[i]);
 
         let mut fitness = vec![INITIAL_ELO; POPULATION_SIZE];
-        while let Ok((bi, wi, outcome)) = rx.recv() {
-            if let Outcome::Winner(winner) = outcome {
+        while let Ok(game) = rx.recv() {
+            if let Outcome::Winner(winner) = game.outcome() {
                 match winner {
-                    Player::Black => elo_update(&mut fitness, bi, wi),
-                    Player::White => elo_update(&mut fitness, wi, bi),
+                    Player::Black => elo_update(&mut fitness, game.black, game.white),
+                    Player::White => elo_update(&mut fitness, game.white, game.black),
                 }
             }
         }
@@ -91,33 +107,34 @@ fn main() {
             })
             .collect::<Vec<_>>();
 
-        let mut next_population = Vec::new();
-        next_population.extend(population.iter().take(ELITISM_SIZE));
-        next_population.extend((0..FRESH_SIZE).map(|_| random_chromosome()));
-
-        while next_population.len() != population.len() {
-            let r = thread_rng().gen_range(0. ..=1.);
-            let i1 = acc_fitness.iter().position(|&f| f >= r).unwrap();
-            let r = thread_rng().gen_range(0. ..=1.);
-            let i2 = acc_fitness.iter().position(|&f| f >= r).unwrap();
-
-            let mut new_individual = random_chromosome();
-            let crossover_point = thread_rng().gen_range(0. ..=1.);
-            for (i, v) in new_individual.iter_mut().enumerate() {
-                let i_crossover = thread_rng().gen_range(0. ..=1.);
-                *v = match i_crossover.partial_cmp(&crossover_point).unwrap() {
-                    Ordering::Less => population[i1][i],
-                    Ordering::Equal => *v,
-                    Ordering::Greater => population[i2][i],
-                };
-
-                let i_mutation = thread_rng().gen_range(-1. ..=1.) * MUTATION_MAGNITUDE;
-                *v += i_mutation;
-            }
-
-            next_population.push(new_individual);
-        }
+        population = rng::with_rng(|rng| {
+            let mut next_population = Vec::new();
+            next_population.extend(population.iter().take(ELITISM_SIZE));
+            next_population.extend((0..FRESH_SIZE).map(|_| random_chromosome(rng)));
+
+            while next_population.len() != population.len() {
+                let r = rng.gen_range(0. ..=1.);
+                let i1 = acc_fitness.iter().position(|&f| f >= r).unwrap();
+                let r = rng.gen_range(0. ..=1.);
+                let i2 = acc_fitness.iter().position(|&f| f >= r).unwrap();
+
+                let mut new_individual = random_chromosome(rng);
+                let crossover_point = rng.gen_range(0. ..=1.);
+                for (i, v) in new_individual.iter_mut().enumerate() {
+                    let i_crossover = rng.gen_range(0. ..=1.);
+                    *v = match i_crossover.partial_cmp(&crossover_point).unwrap() {
+                        Ordering::Less => population[i1][i],
+                        Ordering::Equal => *v,
+                        Ordering::Greater => population[i2][i],
+                    };
+
+                    let i_mutation = rng.gen_range(-1. ..=1.) * MUTATION_MAGNITUDE;
+                    *v += i_mutation;
+                }
 
-        population = next_population;
+                next_population.push(new_individual);
+            }
+            next_population
+        });
     }
 }
@@ -1,88 +1,199 @@
+use clap::{builder::RangedU64ValueParser, value_parser, Parser, ValueEnum};
 use colored::Colorize;
 use game_theory::{
     ai::{
-        AlphaBeta, CornersGreedy, FirstMove, Heuristic, Minimax, RandomMove, ScoreGreedy, Strategy,
+        mcts::{Budget, Playout},
+        AlphaBeta, CornersGreedy, FirstMove, Heuristic, Mcts, Minimax, RandomMove, ScoreGreedy,
+        Strategy,
     },
-    game::{Outcome, Player},
+    game::{record::write_ggf, GameState},
     utils::{
-        elo::{elo_update, INITIAL_ELO},
-        tournament::run_tournament,
+        rating::Results,
+        rng,
+        tournament::{openings_from_transcripts, random_openings, run_schedule, Schedule},
     },
 };
-use std::time::Duration;
-
-fn calculate_ratings(name: &str, strats: &[&dyn Strategy], timeout: Duration) {
-    println!("{}", name.bright_blue().bold());
-    let rx = run_tournament(strats.len(), timeout, |i| strats[i]);
-
-    let mut ratings = vec![INITIAL_ELO; strats.len()];
-    let mut games = vec![0; strats.len()];
-    let mut wins = vec![0; strats.len()];
-    let mut total_games = 0;
-    while let Ok((bi, wi, outcome)) = rx.recv() {
-        if let Outcome::Winner(winner) = outcome {
-            match winner {
-                Player::Black => {
-                    elo_update(&mut ratings, bi, wi);
-                    wins[bi] += 1;
-                }
-                Player::White => {
-                    elo_update(&mut ratings, wi, bi);
-                    wins[wi] += 1;
-                }
-            }
-            games[bi] += 1;
-            games[wi] += 1;
+use std::{
+    fs::{self, File},
+    path::PathBuf,
+};
+
+#[derive(Clone, ValueEnum)]
+enum ScheduleArg {
+    RoundRobin,
+    Gauntlet,
+    Swiss,
+}
+
+#[derive(Parser)]
+struct Args {
+    /// Seed for all random choices, so that runs can be repeated
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Which strategies meet, the gauntlet pits the first one against the rest
+    #[arg(long, default_value = "round-robin")]
+    schedule: ScheduleArg,
+
+    /// Rounds of the Swiss schedule
+    #[arg(long, default_value_t = 5, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    rounds: usize,
+
+    /// Number of random openings, each played twice by every pairing
+    #[arg(long, default_value_t = 4, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    openings: usize,
+
+    /// Random moves played from the standard position in each opening
+    #[arg(long, default_value_t = 4, value_parser = value_parser!(i32).range(1..=20))]
+    opening_moves: i32,
+
+    /// File with one opening transcript per line to use instead of random openings
+    #[arg(long, conflicts_with_all = ["openings", "opening_moves"])]
+    opening_file: Option<PathBuf>,
+
+    /// File to write all games to, in the Generic Game Format
+    #[arg(long)]
+    games: Option<PathBuf>,
+}
+
+impl Args {
+    const fn schedule(&self) -> Schedule {
+        match self.schedule {
+            ScheduleArg::RoundRobin => Schedule::RoundRobin,
+            ScheduleArg::Gauntlet => Schedule::Gauntlet,
+            ScheduleArg::Swiss => Schedule::Swiss {
+                rounds: self.rounds,
+            },
         }
-        total_games += 1;
     }
 
-    println!("Played {total_games} games!");
-    let mut indices = (0..strats.len()).collect::<Vec<_>>();
-    indices.sort_by_key(|i| -ratings[*i]);
-    for (num, i) in indices.into_iter().enumerate() {
-        println!(
-            "{:>2}. {:^25} {:>4} MMR, {:>4.1}% WR",
-            num + 1,
-            strats[i].to_string(),
-            ratings[i],
-            100. * wins[i] as f64 / games[i] as f64
+    fn openings(&self) -> Result<Vec<GameState>, String> {
+        let Some(path) = &self.opening_file else {
+            return Ok(random_openings(self.openings, self.opening_moves));
+        };
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let transcripts = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        openings_from_transcripts(transcripts).map_err(|e| e.to_string())
+    }
+}
+
+/// Settings shared by all tournaments of a run.
+struct Tournaments {
+    schedule: Schedule,
+    openings: Vec<GameState>,
+    games_file: Option<File>,
+}
+
+impl Tournaments {
+    fn calculate_ratings(&mut self, name: &str, strats: &[&dyn Strategy]) {
+        println!("{}", name.bright_blue().bold());
+        let rx = run_schedule(strats.len(), self.schedule, &self.openings, |i| strats[i]);
+        let games = rx.iter().collect::<Vec<_>>();
+        if let Some(file) = &mut self.games_file {
+            if let Err(error) = write_ggf(games.iter().map(|game| &game.record), file) {
+                eprintln!("Games can't be written: {error}");
+            }
+        }
+        let results = Results::from_games(
+            strats.len(),
+            games
+                .iter()
+                .map(|game| (game.black, game.white, game.outcome())),
         );
+        let table = results.fit();
+        let ranking = table.ranking();
+
+        let total_games = table.ratings.iter().map(|r| r.games).sum::<u32>() / 2;
+        println!("Played {total_games} games!");
+        for (num, &i) in ranking.iter().enumerate() {
+            let rating = &table.ratings[i];
+            println!(
+                "{:>2}. {:^25} {:>+6.0} ± {:>3.0} Elo, {:>5.1}% score, {:>4.1}% draws",
+                num + 1,
+                strats[i].to_string(),
+                rating.elo,
+                rating.margin,
+                100. * rating.score(),
+                100. * rating.draws as f64 / rating.games as f64
+            );
+        }
+
+        println!("Likelihood of superiority:");
+        print!("{:>4}", "");
+        for num in 1..=ranking.len() {
+            print!("{num:>6}");
+        }
+        println!();
+        for (num, &i) in ranking.iter().enumerate() {
+            print!("{:>3}.", num + 1);
+            for &j in &ranking {
+                if i == j {
+                    print!("{:>6}", "-");
+                } else {
+                    print!("{:>5.1}%", 100. * table.los[i][j]);
+                }
+            }
+            println!();
+        }
     }
 }
 
 fn main() {
-    calculate_ratings(
+    let args = Args::parse();
+    if let Some(seed) = args.seed {
+        rng::seed(seed);
+    }
+    let openings = match args.openings() {
+        Ok(openings) => openings,
+        Err(error) => {
+            eprintln!("Invalid opening file: {error}");
+            return;
+        }
+    };
+    let games_file = match args.games.as_ref().map(File::create).transpose() {
+        Ok(file) => file,
+        Err(error) => {
+            eprintln!("Games file can't be created: {error}");
+            return;
+        }
+    };
+    let mut tournaments = Tournaments {
+        schedule: args.schedule(),
+        openings,
+        games_file,
+    };
+
+    tournaments.calculate_ratings(
         "NAIVE STRATEGIES",
         &[
-            &RandomMove::default(),
-            &FirstMove::default(),
-            &ScoreGreedy::default(),
-            &CornersGreedy::default(),
+            &RandomMove::new(),
+            &FirstMove,
+            &ScoreGreedy::new(),
+            &CornersGreedy::new(),
         ],
-        Duration::from_secs(1),
     );
 
-    calculate_ratings(
+    tournaments.calculate_ratings(
         "MINIMAX VS ALPHA-BETA",
         &[
             &Minimax::new(Heuristic::MaximumDisc, 3),
             &AlphaBeta::new(Heuristic::MaximumDisc, 3),
         ],
-        Duration::from_secs(10),
     );
 
-    calculate_ratings(
+    tournaments.calculate_ratings(
         "WEIGHT MATRIX COMPARISON",
         &[
             &AlphaBeta::new(Heuristic::W_MAGGS, 4),
             &AlphaBeta::new(Heuristic::W_VAISHU_MUTHU, 4),
             &AlphaBeta::new(Heuristic::W_KORMAN, 4),
         ],
-        Duration::from_secs(10),
     );
 
-    calculate_ratings(
+    tournaments.calculate_ratings(
         "MAX DEPTH COMPARISON",
         &[
             &AlphaBeta::new(Heuristic::Korman, 1),
@@ -91,10 +202,9 @@ fn main() {
             &AlphaBeta::new(Heuristic::Korman, 4),
             &AlphaBeta::new(Heuristic::Korman, 5),
         ],
-        Duration::from_secs(30),
     );
 
-    calculate_ratings(
+    tournaments.calculate_ratings(
         "BASIC HEURISTICS",
         &[
             &AlphaBeta::new(Heuristic::MaximumDisc, 3),
@@ -107,14 +217,27 @@ fn main() {
             &AlphaBeta::new(Heuristic::EdgeStability, 3),
             &AlphaBeta::new(Heuristic::Stability, 3),
         ],
-        Duration::from_secs(10),
     );
 
-    calculate_ratings(
+    tournaments.calculate_ratings(
+        "MONTE CARLO TREE SEARCH",
+        &[
+            &Mcts::new(Playout::Random, Budget::Iterations(500)),
+            &Mcts::new(Playout::Random, Budget::Iterations(2000)),
+            &Mcts::new(
+                Playout::Heuristic(Heuristic::W_KORMAN.into()),
+                Budget::Iterations(500),
+            ),
+            &AlphaBeta::new(Heuristic::W_KORMAN, 4),
+            &AlphaBeta::new(Heuristic::Korman, 4),
+        ],
+    );
+
+    tournaments.calculate_ratings(
         "FULL TOURNAMENT",
         &[
-            &RandomMove::default(),
-            &CornersGreedy::default(),
+            &RandomMove::new(),
+            &CornersGreedy::new(),
             &AlphaBeta::new(Heuristic::W_KORMAN, 4),
             &AlphaBeta::new(Heuristic::Stability, 4),
             &AlphaBeta::new(Heuristic::CornerCloseness, 4),
@@ -126,7 +249,7 @@ fn main() {
             &AlphaBeta::new(Heuristic::le148(), 4),
             &AlphaBeta::new(Heuristic::le162(), 4),
             &AlphaBeta::new(Heuristic::le215(), 4),
+            &Mcts::new(Playout::Random, Budget::Iterations(2000)),
         ],
-        Duration::from_secs(300),
     );
 }
@@ -0,0 +1,45 @@
+use clap::{value_parser, Parser};
+use game_theory::{
+    game::{perft::perft, GameState},
+    utils::rng,
+};
+use std::time::Instant;
+
+#[derive(Parser)]
+struct Args {
+    /// Maximum depth in range 1..=20
+    #[arg(short, long, default_value_t = 8, value_parser = value_parser!(u32).range(1..=20))]
+    depth: u32,
+
+    /// Seed for all random choices, so that runs can be repeated
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+fn main() {
+    let args = Args::parse();
+    if let Some(seed) = args.seed {
+        rng::seed(seed);
+    }
+    for (name, gs) in [
+        ("Othello", GameState::othello_initial()),
+        ("Reversi", GameState::reversi_initial()),
+    ] {
+        println!("{name}:");
+        println!(
+            "{:>5} {:>14} {:>12} {:>12} {:>10}",
+            "Depth", "Leaves", "Passes", "Finished", "Time"
+        );
+        for depth in 1..=args.depth {
+            let start = Instant::now();
+            let counts = perft(&gs, depth);
+            println!(
+                "{depth:>5} {:>14} {:>12} {:>12} {:>7} ms",
+                counts.leaves,
+                counts.passes,
+                counts.finished,
+                start.elapsed().as_millis()
+            );
+        }
+    }
+}
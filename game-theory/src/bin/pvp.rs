@@ -1,11 +1,24 @@
+use clap::Parser;
 use game_theory::{
     ai::{PlayerInput, Strategy},
     game::GameState,
+    utils::rng,
 };
 
+#[derive(Parser)]
+struct Args {
+    /// Seed for all random choices, so that runs can be repeated
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
 fn main() {
+    let args = Args::parse();
+    if let Some(seed) = args.seed {
+        rng::seed(seed);
+    }
     let mut gs = GameState::othello_initial();
-    let strategy = PlayerInput::default();
+    let strategy = PlayerInput;
     print!("{}", gs);
     while gs.outcome().is_none() {
         let position = strategy.decide(&gs);
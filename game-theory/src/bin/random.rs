@@ -1,5 +1,17 @@
-use game_theory::game::GameState;
+use clap::Parser;
+use game_theory::{game::GameState, utils::rng};
+
+#[derive(Parser)]
+struct Args {
+    /// Seed for all random choices, so that runs can be repeated
+    #[arg(long)]
+    seed: Option<u64>,
+}
 
 fn main() {
+    let args = Args::parse();
+    if let Some(seed) = args.seed {
+        rng::seed(seed);
+    }
     println!("{}", GameState::random_state_between_inc(1, 60));
 }
@@ -1,10 +1,15 @@
 use clap::{value_parser, Parser};
 use colored::Colorize;
 use game_theory::{
-    game::{GameState, Player, BOARD_SIDE, BOARD_SQUARES},
+    ai::{endgame::SolveMode, EndgameSolver, Heuristic},
+    game::{BoardError, GameState, Player, BOARD_SIDE, BOARD_SQUARES},
     utils::{
-        cli::{build_strategy, HeuristicArg, CRITICAL, ERROR, HEURISTIC_LIST, INFO, OK, WARN},
-        styles::strip_string,
+        cli::{
+            build_strategy, build_timed_strategy, parse_heuristic, CRITICAL, ERROR, HEURISTIC_LIST,
+            INFO, OK, WARN,
+        },
+        rng,
+        styles::strip_ansi,
     },
 };
 use std::{
@@ -17,14 +22,14 @@ const VERIFICATION_TIMEOUT: Duration = Duration::from_secs(5);
 #[derive(Parser)]
 #[clap(after_help = &*HEURISTIC_LIST)]
 struct Args {
-    /// Heuristic for player 1
+    /// Heuristic for player 1, a name or an expression
     #[arg(
         long = "bh",
         default_value = "korman",
-        hide_possible_values = true,
+        value_parser = parse_heuristic,
         help_heading = "Player 1"
     )]
-    black_heuristic: HeuristicArg,
+    black_heuristic: Heuristic,
 
     /// Max recursion depth for player 1 in range 1..=10
     #[arg(long = "bd", default_value_t = 5, value_parser = value_parser!(u32).range(1..=10), help_heading = "Player 1")]
@@ -34,14 +39,14 @@ struct Args {
     #[arg(long = "bm", help_heading = "Player 1")]
     no_black_pruning: bool,
 
-    /// Heuristic for player 2
+    /// Heuristic for player 2, a name or an expression
     #[arg(
         long = "wh",
         default_value = "korman",
-        hide_possible_values = true,
+        value_parser = parse_heuristic,
         help_heading = "Player 2"
     )]
-    white_heuristic: HeuristicArg,
+    white_heuristic: Heuristic,
 
     /// Max recursion depth for player 2 in range 1..=10
     #[arg(long = "wd", default_value_t = 5, value_parser = value_parser!(u32).range(1..=10), help_heading = "Player 2")]
@@ -51,6 +56,22 @@ struct Args {
     #[arg(long = "wm", help_heading = "Player 2")]
     no_white_pruning: bool,
 
+    /// Search for a given time per move instead of to a fixed depth (for both players)
+    #[arg(long, value_parser = value_parser!(u64).range(1..), conflicts_with_all = ["black_depth", "white_depth", "no_black_pruning", "no_white_pruning"])]
+    time_ms: Option<u64>,
+
+    /// Prefer faster wins and slower losses over the final disc difference
+    #[arg(long)]
+    fast_wins: bool,
+
+    /// Solve the game perfectly once at most this many squares are empty
+    #[arg(long, value_parser = value_parser!(u32).range(1..=20))]
+    endgame: Option<u32>,
+
+    /// Only prove the win/loss/draw result in the endgame instead of the exact score
+    #[arg(long, requires = "endgame")]
+    wld: bool,
+
     /// Don't print the initial info
     #[arg(short = 'i', long)]
     no_initial: bool,
@@ -58,17 +79,33 @@ struct Args {
     /// Don't verify the given game state
     #[arg(short = 'v', long)]
     no_verification: bool,
+
+    /// Seed for all random choices, so that runs can be repeated
+    #[arg(long)]
+    seed: Option<u64>,
 }
 
-fn board_prompt() -> Option<GameState> {
-    println!("Enter the board string:");
+fn board_prompt() -> Result<GameState, BoardError> {
+    println!("Enter the board string, then an empty line unless it ends with B or W:");
     let mut board_str = String::with_capacity(BOARD_SQUARES);
-    while board_str.len() < BOARD_SQUARES {
+    let mut squares = 0;
+    // Reading stops after the player to move, or an empty line once all squares are given
+    while squares <= BOARD_SQUARES {
         let mut line = String::with_capacity(BOARD_SIDE);
-        stdin().read_line(&mut line).unwrap();
-        board_str += &strip_string(&line);
+        if stdin().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        let count = strip_ansi(&line)
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .count();
+        if count == 0 && squares >= BOARD_SQUARES {
+            break;
+        }
+        squares += count;
+        board_str += &line;
     }
-    GameState::from_board_str_unverified(&board_str)
+    board_str.parse()
 }
 
 fn verify_board(gs: &GameState) {
@@ -93,25 +130,53 @@ fn verify_board(gs: &GameState) {
 
 fn main() {
     let args = Args::parse();
-    let Some(mut gs) = board_prompt() else {
-        println!("{} Invalid board string! Aborting...", *CRITICAL);
-        return;
+    if let Some(seed) = args.seed {
+        rng::seed(seed);
+    }
+    let mut gs = match board_prompt() {
+        Ok(gs) => gs,
+        Err(error) => {
+            println!("{} Invalid board string: {error}! Aborting...", *CRITICAL);
+            return;
+        }
     };
 
-    let black_strat = build_strategy(
-        args.black_heuristic,
-        args.black_depth,
-        args.no_black_pruning,
-    );
-    let white_strat = build_strategy(
-        args.white_heuristic,
-        args.white_depth,
-        args.no_white_pruning,
-    );
+    let (black_strat, white_strat) = match args.time_ms {
+        Some(time_ms) => (
+            build_timed_strategy(args.black_heuristic, time_ms, args.fast_wins),
+            build_timed_strategy(args.white_heuristic, time_ms, args.fast_wins),
+        ),
+        None => (
+            build_strategy(
+                args.black_heuristic,
+                args.black_depth,
+                args.no_black_pruning,
+                args.fast_wins,
+            ),
+            build_strategy(
+                args.white_heuristic,
+                args.white_depth,
+                args.no_white_pruning,
+                args.fast_wins,
+            ),
+        ),
+    };
+
+    let endgame = args.endgame.map(|max_empties| {
+        let mode = if args.wld {
+            SolveMode::WinLossDraw
+        } else {
+            SolveMode::ExactScore
+        };
+        EndgameSolver::new(max_empties, mode)
+    });
 
     if !args.no_initial {
         println!("{} Player 1: {}", *INFO, black_strat);
         println!("{} Player 2: {}", *INFO, white_strat);
+        if let Some(endgame) = &endgame {
+            println!("{} Endgame: {}", *INFO, endgame);
+        }
         println!("{} Recognized game state:", *INFO);
         print!("{gs}");
     }
@@ -121,7 +186,21 @@ fn main() {
     }
 
     let start = Instant::now();
+    let mut endgame_solved = false;
     while gs.outcome().is_none() {
+        if let Some(result) = endgame
+            .as_ref()
+            .filter(|_| !endgame_solved)
+            .and_then(|e| e.solve(&gs))
+        {
+            println!("{} Endgame solved: {}", *OK, result);
+            endgame_solved = true;
+            for position in result.principal_variation {
+                gs = gs.make_move(position);
+            }
+            continue;
+        }
+
         let position = match gs.turn() {
             Player::Black => black_strat.decide(&gs),
             Player::White => white_strat.decide(&gs),
@@ -141,4 +220,7 @@ fn main() {
         black_strat.visited() + white_strat.visited(),
         duration.as_millis().to_string().bright_blue().bold()
     );
+    if let Some(endgame) = &endgame {
+        eprintln!("Visited endgame nodes: {:.1e}", endgame.visited());
+    }
 }
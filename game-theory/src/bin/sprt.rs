@@ -0,0 +1,126 @@
+use clap::{builder::RangedU64ValueParser, value_parser, Parser};
+use game_theory::{
+    game::record::write_ggf,
+    utils::{
+        cli::{parse_strategy, StrategySpec, CRITICAL, HEURISTIC_LIST, INFO, OK, WARN},
+        rng,
+        sprt::{Hypothesis, Sprt},
+        tournament::random_openings,
+    },
+};
+use std::{fs::File, path::PathBuf};
+
+fn parse_probability(text: &str) -> Result<f64, String> {
+    match text.parse() {
+        Ok(p) if 0. < p && p < 0.5 => Ok(p),
+        _ => Err(String::from("has to be a probability between 0 and 0.5")),
+    }
+}
+
+#[derive(Parser)]
+#[clap(after_help = &*HEURISTIC_LIST)]
+struct Args {
+    /// Strategy to test, a heuristic with a depth or a time, like korman@5 or stab@100ms
+    #[arg(value_parser = parse_strategy)]
+    candidate: StrategySpec,
+
+    /// Strategy to compare against, given the same way
+    #[arg(value_parser = parse_strategy)]
+    baseline: StrategySpec,
+
+    /// Elo difference of the null hypothesis
+    #[arg(long, default_value_t = 0., allow_negative_numbers = true)]
+    elo0: f64,
+
+    /// Elo difference of the alternative hypothesis
+    #[arg(long, default_value_t = 10., allow_negative_numbers = true)]
+    elo1: f64,
+
+    /// Probability of accepting the alternative hypothesis when the null one holds
+    #[arg(long, default_value_t = 0.05, value_parser = parse_probability)]
+    alpha: f64,
+
+    /// Probability of accepting the null hypothesis when the alternative one holds
+    #[arg(long, default_value_t = 0.05, value_parser = parse_probability)]
+    beta: f64,
+
+    /// Random openings per batch, each played twice with colours swapped
+    #[arg(long, default_value_t = 8, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    batch: usize,
+
+    /// Random moves played from the standard position in each opening
+    #[arg(long, default_value_t = 4, value_parser = value_parser!(i32).range(1..=20))]
+    opening_moves: i32,
+
+    /// Stop without a result after this many games
+    #[arg(long)]
+    max_games: Option<u32>,
+
+    /// Prefer faster wins and slower losses over the final disc difference
+    #[arg(long)]
+    fast_wins: bool,
+
+    /// File to write all games to, in the Generic Game Format
+    #[arg(long)]
+    games: Option<PathBuf>,
+
+    /// Seed for all random choices, so that runs can be repeated
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+fn main() {
+    let args = Args::parse();
+    if let Some(seed) = args.seed {
+        rng::seed(seed);
+    }
+    if args.elo0 >= args.elo1 {
+        eprintln!("Elo of the null hypothesis has to be lower than of the alternative one!");
+        return;
+    }
+
+    let mut games_file = match args.games.as_ref().map(File::create).transpose() {
+        Ok(file) => file,
+        Err(error) => {
+            eprintln!("{} Games file can't be created: {error}", *CRITICAL);
+            return;
+        }
+    };
+
+    let candidate = args.candidate.build(args.fast_wins);
+    let baseline = args.baseline.build(args.fast_wins);
+    let sprt = Sprt::new(args.elo0, args.elo1, args.alpha, args.beta);
+    let (lower, upper) = sprt.bounds();
+    println!("{} {candidate} vs {baseline}", *INFO);
+    println!(
+        "{} H0: {:+} Elo, H1: {:+} Elo, LLR bounds [{lower:.3}, {upper:.3}]",
+        *INFO, args.elo0, args.elo1
+    );
+
+    let result = sprt.run(
+        candidate.as_ref(),
+        baseline.as_ref(),
+        || random_openings(args.batch, args.opening_moves),
+        args.max_games,
+        |status, games| {
+            println!("{} {status}", *INFO);
+            if let Some(file) = &mut games_file {
+                let records = games.iter().map(|game| &game.record);
+                if let Err(error) = write_ggf(records, file) {
+                    eprintln!("{} Games can't be written: {error}", *WARN);
+                }
+            }
+        },
+    );
+    match result {
+        Some(Hypothesis::H0) => println!(
+            "{} H0 accepted, {candidate} is at most {:+} Elo stronger",
+            *OK, args.elo0
+        ),
+        Some(Hypothesis::H1) => println!(
+            "{} H1 accepted, {candidate} is at least {:+} Elo stronger",
+            *OK, args.elo1
+        ),
+        None => println!("{} No hypothesis accepted within the game limit", *WARN),
+    }
+}
@@ -1,14 +1,69 @@
+use clap::Parser;
 use game_theory::{
-    ai::{RandomMove, Strategy},
-    game::{GameState, Player, BOARD_SQUARES},
+    ai::{AlphaBeta, Heuristic, MoveOrdering, RandomMove, Strategy, TreeVisitingStrategy},
+    game::{zobrist::ZobristBuildHasher, GameState, Player, BOARD_SQUARES},
+    utils::rng,
 };
 use std::{cmp::Ordering, collections::HashSet};
 
 const SAMPLE_SIZE: usize = 10_000_000;
+const ORDERING_SAMPLE_SIZE: usize = 200;
+const ORDERING_DEPTH: u32 = 5;
+
+#[derive(Parser)]
+struct Args {
+    /// Seed for all random choices, so that runs can be repeated
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+fn ordering_statistics() {
+    let states = (0..ORDERING_SAMPLE_SIZE)
+        .map(|_| GameState::random_state_between_inc(1, 50))
+        .filter(|gs| gs.outcome().is_none())
+        .collect::<Vec<_>>();
+
+    let single = |f: fn(&mut MoveOrdering)| {
+        let mut ordering = MoveOrdering::NONE;
+        f(&mut ordering);
+        ordering
+    };
+    let orderings = [
+        MoveOrdering::NONE,
+        single(|o| o.hash_move = true),
+        single(|o| o.killers = true),
+        single(|o| o.history = true),
+        single(|o| o.static_order = true),
+        MoveOrdering::ALL,
+    ];
+
+    println!("Average visited nodes at depth {ORDERING_DEPTH}:");
+    let mut baseline = None;
+    for ordering in orderings {
+        let strategy = AlphaBeta::new(Heuristic::Korman, ORDERING_DEPTH).with_ordering(ordering);
+        for gs in states.iter() {
+            let _ = strategy.decide(gs);
+        }
+        let average = strategy.visited() as f64 / states.len() as f64;
+        let baseline = *baseline.get_or_insert(average);
+        println!(
+            "{:>28}: {:>9.1} ({:>5.1}%)",
+            ordering.to_string(),
+            average,
+            100. * average / baseline
+        );
+    }
+}
 
 fn main() {
-    let strategy = RandomMove::default();
-    let mut states = HashSet::new();
+    let args = Args::parse();
+    if let Some(seed) = args.seed {
+        rng::seed(seed);
+    }
+    ordering_statistics();
+
+    let strategy = RandomMove::new();
+    let mut states = HashSet::with_hasher(ZobristBuildHasher::default());
     states.insert(GameState::reversi_initial());
 
     while states.len() < SAMPLE_SIZE {
@@ -19,6 +74,18 @@ fn main() {
         }
     }
 
+    let unique_states = states
+        .iter()
+        .map(|gs| gs.canonical().0)
+        .collect::<HashSet<_, ZobristBuildHasher>>()
+        .len();
+    println!(
+        "Unique states up to symmetry: {} of {} ({:.2}%)",
+        unique_states,
+        states.len(),
+        100. * unique_states as f64 / states.len() as f64
+    );
+
     let mut disc_counts_correct = 0;
     let mut occupied_parity_correct = 0;
     let mut max_branching_factor = 0;
@@ -42,7 +109,7 @@ fn main() {
             Ordering::Greater => Player::White,
         };
 
-        let occupied_parity_turn = if occupied_squares % 2 == 0 {
+        let occupied_parity_turn = if occupied_squares.is_multiple_of(2) {
             Player::Black
         } else {
             Player::White
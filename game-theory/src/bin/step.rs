@@ -1,7 +1,11 @@
 use clap::{value_parser, ArgAction, Parser, ValueEnum};
 use game_theory::{
+    ai::Heuristic,
     game::{GameState, Player, Position},
-    utils::cli::{build_strategy, HeuristicArg, HEURISTIC_LIST},
+    utils::{
+        cli::{build_strategy, build_timed_strategy, parse_heuristic, HEURISTIC_LIST},
+        rng,
+    },
 };
 use std::{io::stdin, thread, time::Duration};
 
@@ -27,9 +31,9 @@ struct Args {
     #[arg(short, long)]
     player: PlayerArg,
 
-    /// Heuristic function for the player
-    #[arg(short, long, default_value = "korman", hide_possible_values = true)]
-    heuristic: HeuristicArg,
+    /// Heuristic function for the player, a name or an expression
+    #[arg(short, long, default_value = "korman", value_parser = parse_heuristic)]
+    heuristic: Heuristic,
 
     /// Max recursion depth for the player
     #[arg(short, long, default_value_t = 5, value_parser = value_parser!(u32).range(1..=10))]
@@ -39,6 +43,18 @@ struct Args {
     #[arg(short, long)]
     no_pruning: bool,
 
+    /// Prefer faster wins and slower losses over the final disc difference
+    #[arg(long)]
+    fast_wins: bool,
+
+    /// Search for a given time per move instead of to a fixed depth
+    #[arg(short, long, value_parser = value_parser!(u64).range(1..), conflicts_with_all = ["depth", "no_pruning"])]
+    time_ms: Option<u64>,
+
+    /// Seed for all random choices, so that runs can be repeated
+    #[arg(long)]
+    seed: Option<u64>,
+
     /// Print help
     #[arg(long, action = ArgAction::Help)]
     help: Option<bool>,
@@ -57,8 +73,14 @@ fn read_position() -> Position {
 
 fn main() {
     let args = Args::parse();
+    if let Some(seed) = args.seed {
+        rng::seed(seed);
+    }
     let my_player: Player = args.player.into();
-    let strategy = build_strategy(args.heuristic, args.depth, args.no_pruning);
+    let strategy = match args.time_ms {
+        Some(time_ms) => build_timed_strategy(args.heuristic, time_ms, args.fast_wins),
+        None => build_strategy(args.heuristic, args.depth, args.no_pruning, args.fast_wins),
+    };
 
     let mut gs = GameState::othello_initial();
     if my_player == Player::White {
@@ -68,7 +90,9 @@ fn main() {
     while gs.outcome().is_none() {
         let in_control = gs.turn() == my_player;
         let position = if in_control {
-            strategy.decide(&gs)
+            let analysis = strategy.analyze(&gs);
+            eprintln!("Analysis: {analysis}");
+            analysis.position
         } else {
             read_position()
         };
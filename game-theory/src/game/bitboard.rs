@@ -1,4 +1,4 @@
-use super::{Position, BOARD_SQUARES};
+use super::{Position, BOARD_SIDE};
 
 pub type Bitboard = u64;
 
@@ -6,6 +6,7 @@ pub const EMPTY: Bitboard = 0x0000000000000000;
 pub const FULL: Bitboard = !EMPTY;
 pub const CENTER: Bitboard = 0x0000001818000000;
 pub const CORNERS: Bitboard = 0x8100000000000081;
+pub const X_SQUARES: Bitboard = 0x0042000000004200;
 pub const EDGES: Bitboard = 0xFF818181818181FF;
 pub const INTERNAL: Bitboard = !EDGES;
 pub const OTHELLO_BLACK_START: Bitboard = 0x0000000810000000;
@@ -22,24 +23,45 @@ pub const fn has(bitboard: Bitboard, position: Position) -> bool {
 }
 
 #[must_use]
-pub fn positions(mut bb: Bitboard) -> Vec<Position> {
-    let mut result = Vec::with_capacity(BOARD_SQUARES);
-    let mut i = 0;
-    while bb != EMPTY {
-        if bb & 1 != EMPTY {
-            result.push(Position::from_index(i));
+pub fn positions(bb: Bitboard) -> Vec<Position> {
+    bits(bb).collect()
+}
+
+/// Iterates over positions of the set bits without allocating, from A1 to H8.
+pub const fn bits(bb: Bitboard) -> Bits {
+    Bits(bb)
+}
+
+#[must_use]
+#[derive(Clone, Copy, Debug)]
+pub struct Bits(Bitboard);
+
+impl Iterator for Bits {
+    type Item = Position;
+
+    fn next(&mut self) -> Option<Position> {
+        if self.0 == EMPTY {
+            return None;
         }
-        bb >>= 1;
-        i += 1;
+        let index = self.0.trailing_zeros() as usize;
+        // Clear the lowest set bit
+        self.0 &= self.0 - 1;
+        Some(Position::from_index(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.0.count_ones() as usize;
+        (count, Some(count))
     }
-    result
 }
 
+impl ExactSizeIterator for Bits {}
+
 #[must_use]
 pub const fn valid_moves(current: Bitboard, opponent: Bitboard) -> Bitboard {
     let occupied = current | opponent;
     if occupied.count_ones() >= 4 {
-        attack_fill(current, opponent)
+        kogge_stone::attack_fill(current, opponent)
     } else {
         CENTER & !occupied
     }
@@ -51,7 +73,7 @@ pub fn make_move(position: Position, current: &mut Bitboard, opponent: &mut Bitb
         panic!("Invalid move!");
     }
 
-    let flipped = all_flipped(position, *current, *opponent);
+    let flipped = kogge_stone::all_flipped(position, *current, *opponent);
     *current |= position;
     *current |= flipped;
     *opponent ^= flipped;
@@ -82,52 +104,166 @@ pub const fn neighbours(bb: Bitboard) -> Bitboard {
         | shift_noea(bb)
 }
 
+/// Discs which can never be flipped again, found by growing the set to a fixpoint.
+///
+/// A disc is stable when it is protected along all 4 lines through it. A line protects it
+/// when the line is full, or when a neighbour on the line is off the board or a stable disc
+/// of the same colour. Not every stable disc is found, but every found disc is stable.
 #[must_use]
-const fn all_flipped(position: Bitboard, current: Bitboard, opponent: Bitboard) -> Bitboard {
+pub fn stable_discs(black: Bitboard, white: Bitboard) -> Bitboard {
     use dumb7fill::*;
-    fill_nort(position, opponent) & fill_sout(current, opponent)
-        | fill_noea(position, opponent) & fill_sowe(current, opponent)
-        | fill_east(position, opponent) & fill_west(current, opponent)
-        | fill_soea(position, opponent) & fill_nowe(current, opponent)
-        | fill_sout(position, opponent) & fill_nort(current, opponent)
-        | fill_sowe(position, opponent) & fill_noea(current, opponent)
-        | fill_west(position, opponent) & fill_east(current, opponent)
-        | fill_nowe(position, opponent) & fill_soea(current, opponent)
+    const VERTICAL_ENDS: Bitboard = 0xFF000000000000FF;
+    const HORIZONTAL_ENDS: Bitboard = !(NOT_A_FILE & NOT_H_FILE);
+
+    type Shift = fn(Bitboard) -> Bitboard;
+    let lines: [(Shift, Shift, Bitboard); 4] = [
+        (shift_nort, shift_sout, VERTICAL_ENDS),
+        (shift_noea, shift_sowe, EDGES),
+        (shift_east, shift_west, HORIZONTAL_ENDS),
+        (shift_soea, shift_nowe, EDGES),
+    ];
+    let occupied = black | white;
+    let protected = lines.map(|(forward, backward, ends)| {
+        // Spread the empty squares along the line, what is left are full lines
+        let mut open = !occupied;
+        for _ in 1..BOARD_SIDE {
+            open |= forward(open) | backward(open);
+        }
+        !open | ends
+    });
+
+    let anchored = |discs: Bitboard, stable: Bitboard| {
+        let stable = stable & discs;
+        lines
+            .iter()
+            .zip(protected)
+            .fold(discs, |discs, (&(forward, backward, _), protected)| {
+                discs & (protected | forward(stable) | backward(stable))
+            })
+    };
+
+    let mut stable = EMPTY;
+    loop {
+        let next = anchored(black, stable) | anchored(white, stable);
+        if next == stable {
+            return stable;
+        }
+        stable = next;
+    }
+}
+
+// https://www.chessprogramming.org/Flipping_Mirroring_and_Rotating
+
+/// Mirrors the board across the horizontal axis, row 1 becomes row 8.
+#[must_use]
+pub const fn flip_vertical(bb: Bitboard) -> Bitboard {
+    bb.swap_bytes()
 }
 
+/// Mirrors the board across the vertical axis, column A becomes column H.
 #[must_use]
-const fn attack_fill(current: Bitboard, opponent: Bitboard) -> Bitboard {
-    use dumb7fill::*;
-    !(current | opponent)
-        & (shift_nort(fill_nort(current, opponent))
-            | shift_noea(fill_noea(current, opponent))
-            | shift_east(fill_east(current, opponent))
-            | shift_soea(fill_soea(current, opponent))
-            | shift_sout(fill_sout(current, opponent))
-            | shift_sowe(fill_sowe(current, opponent))
-            | shift_west(fill_west(current, opponent))
-            | shift_nowe(fill_nowe(current, opponent)))
-}
-
-mod dumb7fill {
+pub const fn flip_horizontal(mut bb: Bitboard) -> Bitboard {
+    const K1: Bitboard = 0x5555555555555555;
+    const K2: Bitboard = 0x3333333333333333;
+    const K4: Bitboard = 0x0F0F0F0F0F0F0F0F;
+    bb = ((bb >> 1) & K1) | ((bb & K1) << 1);
+    bb = ((bb >> 2) & K2) | ((bb & K2) << 2);
+    ((bb >> 4) & K4) | ((bb & K4) << 4)
+}
+
+/// Mirrors the board across the A1-H8 diagonal, swapping rows with columns.
+#[must_use]
+pub const fn flip_diagonal(mut bb: Bitboard) -> Bitboard {
+    const K1: Bitboard = 0x5500550055005500;
+    const K2: Bitboard = 0x3333000033330000;
+    const K4: Bitboard = 0x0F0F0F0F00000000;
+    let mut t = K4 & (bb ^ (bb << 28));
+    bb ^= t ^ (t >> 28);
+    t = K2 & (bb ^ (bb << 14));
+    bb ^= t ^ (t >> 14);
+    t = K1 & (bb ^ (bb << 7));
+    bb ^ t ^ (t >> 7)
+}
+
+/// Mirrors the board across the H1-A8 diagonal.
+#[must_use]
+pub const fn flip_anti_diagonal(bb: Bitboard) -> Bitboard {
+    rotate_180(flip_diagonal(bb))
+}
+
+/// Rotates the board clockwise, as it is printed.
+#[must_use]
+pub const fn rotate_90(bb: Bitboard) -> Bitboard {
+    flip_horizontal(flip_diagonal(bb))
+}
+
+#[must_use]
+pub const fn rotate_180(bb: Bitboard) -> Bitboard {
+    bb.reverse_bits()
+}
+
+#[must_use]
+pub const fn rotate_270(bb: Bitboard) -> Bitboard {
+    flip_vertical(flip_diagonal(bb))
+}
+
+/// Defines `all_flipped` and `attack_fill` in terms of the directional fills of the module.
+macro_rules! fill_operations {
+    () => {
+        /// Discs of the opponent flipped by placing a disc on the given square.
+        #[must_use]
+        pub const fn all_flipped(
+            position: Bitboard,
+            current: Bitboard,
+            opponent: Bitboard,
+        ) -> Bitboard {
+            fill_nort(position, opponent) & fill_sout(current, opponent)
+                | fill_noea(position, opponent) & fill_sowe(current, opponent)
+                | fill_east(position, opponent) & fill_west(current, opponent)
+                | fill_soea(position, opponent) & fill_nowe(current, opponent)
+                | fill_sout(position, opponent) & fill_nort(current, opponent)
+                | fill_sowe(position, opponent) & fill_noea(current, opponent)
+                | fill_west(position, opponent) & fill_east(current, opponent)
+                | fill_nowe(position, opponent) & fill_soea(current, opponent)
+        }
+
+        /// Empty squares where the current player can flip at least one disc.
+        #[must_use]
+        pub const fn attack_fill(current: Bitboard, opponent: Bitboard) -> Bitboard {
+            !(current | opponent)
+                & (shift_nort(fill_nort(current, opponent))
+                    | shift_noea(fill_noea(current, opponent))
+                    | shift_east(fill_east(current, opponent))
+                    | shift_soea(fill_soea(current, opponent))
+                    | shift_sout(fill_sout(current, opponent))
+                    | shift_sowe(fill_sowe(current, opponent))
+                    | shift_west(fill_west(current, opponent))
+                    | shift_nowe(fill_nowe(current, opponent)))
+        }
+    };
+}
+
+pub mod dumb7fill {
     // https://www.chessprogramming.org/Dumb7Fill
     // https://www.chessprogramming.org/General_Setwise_Operations
 
     use super::{Bitboard, FULL};
     use crate::game::BOARD_SIDE;
 
-    // Compass rose is different than in chess, because Reversi ranks grow south, not north.
-    const EAST: i32 = 1; // +1
-    const SOUT: i32 = BOARD_SIDE as i32; // +8
-    const WEST: i32 = -EAST; // -1
-    const NORT: i32 = -SOUT; // -8
-    const SOEA: i32 = SOUT + EAST; // +9
-    const SOWE: i32 = SOUT + WEST; // +7
-    const NOWE: i32 = NORT + WEST; // -9
-    const NOEA: i32 = NORT + EAST; // -7
+    fill_operations!();
 
-    const NOT_A_FILE: Bitboard = 0xFEFEFEFEFEFEFEFE;
-    const NOT_H_FILE: Bitboard = 0x7F7F7F7F7F7F7F7F;
+    // Compass rose is different than in chess, because Reversi ranks grow south, not north.
+    pub(super) const EAST: i32 = 1; // +1
+    pub(super) const SOUT: i32 = BOARD_SIDE as i32; // +8
+    pub(super) const WEST: i32 = -EAST; // -1
+    pub(super) const NORT: i32 = -SOUT; // -8
+    pub(super) const SOEA: i32 = SOUT + EAST; // +9
+    pub(super) const SOWE: i32 = SOUT + WEST; // +7
+    pub(super) const NOWE: i32 = NORT + WEST; // -9
+    pub(super) const NOEA: i32 = NORT + EAST; // -7
+
+    pub(super) const NOT_A_FILE: Bitboard = 0xFEFEFEFEFEFEFEFE;
+    pub(super) const NOT_H_FILE: Bitboard = 0x7F7F7F7F7F7F7F7F;
 
     #[must_use]
     const fn fill(mut gen: Bitboard, mut pro: Bitboard, dir: i32, mask: Bitboard) -> Bitboard {
@@ -187,7 +323,7 @@ mod dumb7fill {
     }
 
     #[must_use]
-    const fn shift(bitboard: Bitboard, by: i32, mask: Bitboard) -> Bitboard {
+    pub(super) const fn shift(bitboard: Bitboard, by: i32, mask: Bitboard) -> Bitboard {
         mask & if by >= 0 {
             bitboard >> by
         } else {
@@ -235,3 +371,197 @@ mod dumb7fill {
         shift(bb, NOEA, NOT_H_FILE)
     }
 }
+
+pub mod kogge_stone {
+    // https://www.chessprogramming.org/Kogge-Stone_Algorithm
+
+    pub use super::dumb7fill::{
+        shift_east, shift_noea, shift_nort, shift_nowe, shift_soea, shift_sout, shift_sowe,
+        shift_west,
+    };
+    use super::{
+        dumb7fill::{
+            shift, EAST, NOEA, NORT, NOT_A_FILE, NOT_H_FILE, NOWE, SOEA, SOUT, SOWE, WEST,
+        },
+        Bitboard, FULL,
+    };
+
+    fill_operations!();
+
+    /// Same result as the Dumb7Fill version, but using 3 parallel steps instead of 6 serial shifts.
+    #[must_use]
+    const fn fill(gen: Bitboard, pro: Bitboard, dir: i32, mask: Bitboard) -> Bitboard {
+        let pro = pro & mask;
+        let mut occluded = gen;
+        occluded |= pro & shift(occluded, dir, FULL);
+        // Squares which continue a run of two propagators
+        let run = pro & shift(pro, dir, FULL);
+        occluded |= run & shift(occluded, 2 * dir, FULL);
+        occluded |= run & shift(occluded, 2 * dir, FULL);
+        // Step past the occluded squares, reaching at most 6 squares like Dumb7Fill
+        shift(occluded, dir, mask) & pro
+    }
+
+    #[must_use]
+    pub const fn fill_east(gen: Bitboard, pro: Bitboard) -> Bitboard {
+        fill(gen, pro, EAST, NOT_H_FILE)
+    }
+
+    #[must_use]
+    pub const fn fill_sout(gen: Bitboard, pro: Bitboard) -> Bitboard {
+        fill(gen, pro, SOUT, FULL)
+    }
+
+    #[must_use]
+    pub const fn fill_west(gen: Bitboard, pro: Bitboard) -> Bitboard {
+        fill(gen, pro, WEST, NOT_A_FILE)
+    }
+
+    #[must_use]
+    pub const fn fill_nort(gen: Bitboard, pro: Bitboard) -> Bitboard {
+        fill(gen, pro, NORT, FULL)
+    }
+
+    #[must_use]
+    pub const fn fill_soea(gen: Bitboard, pro: Bitboard) -> Bitboard {
+        fill(gen, pro, SOEA, NOT_H_FILE)
+    }
+
+    #[must_use]
+    pub const fn fill_sowe(gen: Bitboard, pro: Bitboard) -> Bitboard {
+        fill(gen, pro, SOWE, NOT_A_FILE)
+    }
+
+    #[must_use]
+    pub const fn fill_nowe(gen: Bitboard, pro: Bitboard) -> Bitboard {
+        fill(gen, pro, NOWE, NOT_A_FILE)
+    }
+
+    #[must_use]
+    pub const fn fill_noea(gen: Bitboard, pro: Bitboard) -> Bitboard {
+        fill(gen, pro, NOEA, NOT_H_FILE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GameState, Player, BOARD_SQUARES};
+    use quickcheck_macros::quickcheck;
+
+    #[quickcheck]
+    fn bits_returns_set_positions_in_order(bb: Bitboard) -> bool {
+        let expected = (0..BOARD_SQUARES)
+            .filter(|&i| bb & (1 << i) != EMPTY)
+            .map(Position::from_index)
+            .collect::<Vec<_>>();
+        bits(bb).len() == expected.len() && bits(bb).eq(expected)
+    }
+
+    #[quickcheck]
+    fn kogge_stone_attack_fill_matches_dumb7fill(gs: GameState) -> bool {
+        let current = gs.bb_of(gs.turn());
+        let opponent = gs.bb_of(gs.turn().opponent());
+        kogge_stone::attack_fill(current, opponent) == dumb7fill::attack_fill(current, opponent)
+    }
+
+    #[quickcheck]
+    fn kogge_stone_all_flipped_matches_dumb7fill(gs: GameState) -> bool {
+        let current = gs.bb_of(gs.turn());
+        let opponent = gs.bb_of(gs.turn().opponent());
+        bits(gs.move_bb()).all(|position| {
+            let position = from_pos(position);
+            kogge_stone::all_flipped(position, current, opponent)
+                == dumb7fill::all_flipped(position, current, opponent)
+        })
+    }
+
+    #[quickcheck]
+    fn kogge_stone_fills_match_dumb7fill(gen: Bitboard, pro: Bitboard) -> bool {
+        let pro = pro & !gen;
+        [
+            (
+                kogge_stone::fill_east(gen, pro),
+                dumb7fill::fill_east(gen, pro),
+            ),
+            (
+                kogge_stone::fill_sout(gen, pro),
+                dumb7fill::fill_sout(gen, pro),
+            ),
+            (
+                kogge_stone::fill_west(gen, pro),
+                dumb7fill::fill_west(gen, pro),
+            ),
+            (
+                kogge_stone::fill_nort(gen, pro),
+                dumb7fill::fill_nort(gen, pro),
+            ),
+            (
+                kogge_stone::fill_soea(gen, pro),
+                dumb7fill::fill_soea(gen, pro),
+            ),
+            (
+                kogge_stone::fill_sowe(gen, pro),
+                dumb7fill::fill_sowe(gen, pro),
+            ),
+            (
+                kogge_stone::fill_nowe(gen, pro),
+                dumb7fill::fill_nowe(gen, pro),
+            ),
+            (
+                kogge_stone::fill_noea(gen, pro),
+                dumb7fill::fill_noea(gen, pro),
+            ),
+        ]
+        .into_iter()
+        .all(|(kogge_stone, dumb7fill)| kogge_stone == dumb7fill)
+    }
+
+    /// Discs which change colour in any continuation of the game, found by trying all of them.
+    fn ever_flipped(gs: &GameState, black: Bitboard, white: Bitboard) -> Bitboard {
+        let flipped = black & gs.bb_of(Player::White) | white & gs.bb_of(Player::Black);
+        if gs.outcome().is_some() {
+            return flipped;
+        }
+        bits(gs.move_bb()).fold(flipped, |flipped, position| {
+            flipped | ever_flipped(&gs.make_move(position), black, white)
+        })
+    }
+
+    #[test]
+    fn stable_discs_are_never_flipped() {
+        for _ in 0..200 {
+            let gs = GameState::random_state_between_inc(52, 58);
+            let black = gs.bb_of(Player::Black);
+            let white = gs.bb_of(Player::White);
+            let stable = stable_discs(black, white);
+            assert_eq!(stable & ever_flipped(&gs, black, white), EMPTY, "{gs}");
+        }
+    }
+
+    #[quickcheck]
+    fn stable_discs_include_occupied_corners(gs: GameState) -> bool {
+        let stable = stable_discs(gs.bb_of(Player::Black), gs.bb_of(Player::White));
+        stable & !gs.occupied_bb() == EMPTY && stable & CORNERS == gs.occupied_bb() & CORNERS
+    }
+
+    #[test]
+    fn stable_discs_grow_from_corners_and_full_lines() {
+        let gs = GameState::othello_initial();
+        let black = gs.bb_of(Player::Black);
+        let white = gs.bb_of(Player::White);
+        assert_eq!(stable_discs(black, white), EMPTY);
+        // Discs along the first row anchored to the A1 corner, the H1 square is empty
+        assert_eq!(stable_discs(0x0F, 0x30), 0x0F);
+        // A full edge is stable whatever the colours, a full inner row is not
+        assert_eq!(stable_discs(0x55, 0xAA), 0xFF);
+        assert_eq!(stable_discs(0x55000000, 0xAA000000), EMPTY);
+    }
+
+    #[test]
+    fn fills_stop_after_six_squares() {
+        let gen = 0xFF00000000000000;
+        assert_eq!(dumb7fill::fill_sout(gen, !gen), 0x00FFFFFFFFFFFF00);
+        assert_eq!(kogge_stone::fill_sout(gen, !gen), 0x00FFFFFFFFFFFF00);
+    }
+}
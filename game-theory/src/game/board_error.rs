@@ -0,0 +1,62 @@
+use super::{Position, BOARD_SIDE, BOARD_SQUARES};
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+/// Reason why a board string could not be parsed, rows and columns are numbered from 0.
+#[must_use]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoardError {
+    /// Board doesn't have exactly 64 squares, the number of found squares is given.
+    WrongLength(usize),
+    /// Square is neither `0`, `1` nor `2`.
+    BadCharacter { row: usize, col: usize, found: char },
+    /// Both players have a disc on the same square.
+    OverlappingDiscs { row: usize, col: usize },
+    /// Player inferred from the disc count can't move, so the board is only reachable after a pass.
+    UnknownTurn,
+}
+
+impl BoardError {
+    pub(super) const fn bad_character(index: usize, found: char) -> Self {
+        BoardError::BadCharacter {
+            row: index / BOARD_SIDE,
+            col: index % BOARD_SIDE,
+            found,
+        }
+    }
+
+    pub(super) const fn overlapping_discs(index: usize) -> Self {
+        BoardError::OverlappingDiscs {
+            row: index / BOARD_SIDE,
+            col: index % BOARD_SIDE,
+        }
+    }
+}
+
+impl Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let square = |row: usize, col: usize| {
+            let position = Position::from_index(row * BOARD_SIDE + col);
+            format!("row {}, column {} ({position})", row + 1, col + 1)
+        };
+        match *self {
+            BoardError::WrongLength(found) => {
+                write!(f, "expected {BOARD_SQUARES} squares, found {found}")
+            }
+            BoardError::BadCharacter { row, col, found } => {
+                write!(f, "unexpected character '{found}' at {}", square(row, col))
+            }
+            BoardError::OverlappingDiscs { row, col } => {
+                write!(f, "both players have a disc at {}", square(row, col))
+            }
+            BoardError::UnknownTurn => write!(
+                f,
+                "player to move can't be inferred, add B or W after the board"
+            ),
+        }
+    }
+}
+
+impl Error for BoardError {}
@@ -1,34 +1,56 @@
 use crate::{
-    ai::{RandomMove, Strategy},
+    ai::RandomMove,
     game::{
         bitboard::{self as bb, Bitboard},
-        Outcome, Player, Position, Square, BOARD_SIDE, BOARD_SQUARES,
+        zobrist::{self, ZobristBuildHasher},
+        BoardError, Outcome, Player, Position, Square, Symmetry, BOARD_SIDE, BOARD_SQUARES,
+    },
+    utils::{
+        rng,
+        styles::{strip_ansi, strip_string, EMPTY_BG, VALID_FG},
     },
-    utils::styles::{strip_string, EMPTY_BG, VALID_FG},
 };
 use colored::Colorize;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use std::{
     cmp::Ordering,
     collections::HashSet,
     fmt::{self, Display},
-    hash::Hash,
+    hash::{Hash, Hasher},
+    str::FromStr,
     time::{Duration, Instant},
 };
 
 #[must_use]
-#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct GameState {
     turn: Player,
     black: Bitboard,
     white: Bitboard,
+    /// Zobrist key of the other fields, updated with every change.
+    key: u64,
 }
 
 impl GameState {
+    const fn new(turn: Player, black: Bitboard, white: Bitboard) -> Self {
+        Self {
+            turn,
+            black,
+            white,
+            key: zobrist::key(turn, black, white),
+        }
+    }
+
     pub const fn turn(&self) -> Player {
         self.turn
     }
 
+    /// Position key, which stays the same between runs.
+    #[must_use]
+    pub const fn zobrist_key(&self) -> u64 {
+        self.key
+    }
+
     #[must_use]
     pub const fn bb_of(&self, player: Player) -> Bitboard {
         match player {
@@ -59,34 +81,42 @@ impl GameState {
     }
 
     pub const fn reversi_initial() -> Self {
-        Self {
-            turn: Player::Black,
-            black: bb::EMPTY,
-            white: bb::EMPTY,
-        }
+        Self::new(Player::Black, bb::EMPTY, bb::EMPTY)
     }
 
     pub const fn othello_initial() -> Self {
-        Self {
-            turn: Player::Black,
-            black: bb::OTHELLO_BLACK_START,
-            white: bb::OTHELLO_WHITE_START,
-        }
+        Self::new(
+            Player::Black,
+            bb::OTHELLO_BLACK_START,
+            bb::OTHELLO_WHITE_START,
+        )
+    }
+
+    /// Builds a state from discs of both players, the turn is passed if the player can't move.
+    pub(super) fn from_bitboards(turn: Player, black: Bitboard, white: Bitboard) -> Self {
+        assert_eq!(black & white, bb::EMPTY);
+        let mut result = Self::new(turn, black, white);
+        result.pass_if_required();
+        result
     }
 
+    /// Plays random moves from the empty board, using the generator of the current thread.
     pub fn random_state_between_inc(min_turn: i32, max_turn: i32) -> Self {
+        rng::with_rng(|rng| Self::random_state_with_rng(min_turn, max_turn, rng))
+    }
+
+    pub fn random_state_with_rng(min_turn: i32, max_turn: i32, rng: &mut impl Rng) -> Self {
         assert!((-3..=60).contains(&min_turn));
         assert!((min_turn..=60).contains(&max_turn));
 
         // Move from Othello move space to Reversi round space
-        let n = thread_rng().gen_range(min_turn..=max_turn) + 3;
-        let strategy = RandomMove::default();
+        let n = rng.gen_range(min_turn..=max_turn) + 3;
         let mut gs = Self::reversi_initial();
         for _ in 0..n {
             if gs.move_bb() == bb::EMPTY {
-                return Self::random_state_between_inc(min_turn, max_turn);
+                return Self::random_state_with_rng(min_turn, max_turn, rng);
             }
-            gs = gs.make_move(strategy.decide(&gs));
+            gs = gs.make_move(RandomMove::choose(&gs, rng));
         }
         gs
     }
@@ -107,16 +137,21 @@ impl GameState {
 
     #[must_use]
     pub fn moves(&self) -> Vec<Position> {
-        bb::positions(self.move_bb())
+        bb::bits(self.move_bb()).collect()
+    }
+
+    fn switch_turn(&mut self) {
+        self.turn = self.turn.opponent();
+        self.key ^= zobrist::WHITE_TO_MOVE;
     }
 
     fn pass_if_required(&mut self) {
         if self.move_bb() == bb::EMPTY {
             // No moves for opponent, pass
-            self.turn = self.turn.opponent();
+            self.switch_turn();
             if self.move_bb() == bb::EMPTY {
                 // No moves again, game is over, correct the player
-                self.turn = self.turn.opponent();
+                self.switch_turn();
             }
         }
     }
@@ -128,7 +163,10 @@ impl GameState {
             Player::White => bb::make_move(position, &mut next_state.white, &mut next_state.black),
         };
 
-        next_state.turn = next_state.turn.opponent();
+        // Only the placed and flipped discs change their keys
+        next_state.key ^= zobrist::discs_key(Player::Black, self.black ^ next_state.black)
+            ^ zobrist::discs_key(Player::White, self.white ^ next_state.white);
+        next_state.switch_turn();
         next_state.pass_if_required();
 
         assert_eq!(next_state.black & next_state.white, bb::EMPTY);
@@ -136,6 +174,26 @@ impl GameState {
         next_state
     }
 
+    /// Returns the state with both players' discs transformed by the symmetry.
+    pub const fn transformed(&self, symmetry: Symmetry) -> Self {
+        Self::new(
+            self.turn,
+            symmetry.apply(self.black),
+            symmetry.apply(self.white),
+        )
+    }
+
+    /// Returns the smallest of the symmetric states together with the symmetry leading to it.
+    ///
+    /// Moves of the canonical state can be mapped back with `Symmetry::revert_position`.
+    pub fn canonical(&self) -> (Self, Symmetry) {
+        Symmetry::ALL
+            .into_iter()
+            .map(|symmetry| (self.transformed(symmetry), symmetry))
+            .min_by_key(|(gs, _)| (gs.black, gs.white))
+            .unwrap()
+    }
+
     #[must_use]
     pub fn outcome(&self) -> Option<Outcome> {
         if self.move_bb() != bb::EMPTY {
@@ -154,6 +212,11 @@ impl GameState {
         )
     }
 
+    /// Reads 64 squares, ignoring every character other than 0, 1 and 2, the player to move
+    /// is inferred from the number of discs.
+    ///
+    /// Parsing with `str::parse` is stricter, reads the player to move and reports why
+    /// a board is invalid.
     #[must_use]
     pub fn from_board_str_unverified(board_str: &str) -> Option<Self> {
         let board_str = strip_string(board_str);
@@ -163,29 +226,61 @@ impl GameState {
 
         let mut black = bb::EMPTY;
         let mut white = bb::EMPTY;
-        board_str.chars().enumerate().for_each(|(i, c)| match c {
-            '0' => (),
-            '1' => black |= 1 << i,
-            '2' => white |= 1 << i,
-            _ => unreachable!(), // strip_string should only leave 0, 1 and 2
-        });
-
-        let turn = if (black | white).count_ones() % 2 == 0 {
+        for (i, c) in board_str.chars().enumerate() {
+            match c {
+                '1' => black |= 1 << i,
+                '2' => white |= 1 << i,
+                _ => (),
+            }
+        }
+        let turn = if (black | white).count_ones().is_multiple_of(2) {
             Player::Black
         } else {
             Player::White
         };
-        let mut result = GameState { turn, black, white };
-        result.pass_if_required();
-        assert_eq!(result.black & result.white, bb::EMPTY);
-        Some(result)
+        Some(Self::from_bitboards(turn, black, white))
+    }
+
+    /// Builds a state from discs of both players.
+    ///
+    /// If the player to move is not given, it is inferred from the number of discs,
+    /// which is only correct if nobody has passed yet.
+    pub fn from_discs(
+        black: Bitboard,
+        white: Bitboard,
+        turn: Option<Player>,
+    ) -> Result<Self, BoardError> {
+        let overlapping = black & white;
+        if overlapping != bb::EMPTY {
+            return Err(BoardError::overlapping_discs(
+                overlapping.trailing_zeros() as usize
+            ));
+        }
+
+        let turn = match turn {
+            Some(turn) => turn,
+            None => {
+                let turn = if (black | white).count_ones().is_multiple_of(2) {
+                    Player::Black
+                } else {
+                    Player::White
+                };
+                let inferred = Self::new(turn, black, white);
+                let opponent = Self::new(turn.opponent(), black, white);
+                if inferred.move_bb() == bb::EMPTY && opponent.move_bb() != bb::EMPTY {
+                    return Err(BoardError::UnknownTurn);
+                }
+                turn
+            }
+        };
+        Ok(Self::from_bitboards(turn, black, white))
     }
 
     #[must_use]
     fn original_discs(&self) -> (Bitboard, Bitboard) {
         let mut black = bb::EMPTY;
         let mut white = bb::EMPTY;
-        for pos in bb::positions(self.occupied_bb()) {
+        for pos in bb::bits(self.occupied_bb()) {
             if !bb::diagonals(pos)
                 .into_iter()
                 .any(|diagonal| (diagonal & self.occupied_bb()).count_ones() == 2)
@@ -205,7 +300,7 @@ impl GameState {
         let (og_black, og_white) = self.original_discs();
 
         let mut stack = Vec::from([GameState::reversi_initial()]);
-        let mut visited = HashSet::new();
+        let mut visited = HashSet::with_hasher(ZobristBuildHasher::default());
         while let Some(current) = stack.pop() {
             if current == *self {
                 return Some(true);
@@ -237,6 +332,49 @@ impl GameState {
     }
 }
 
+impl Hash for GameState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.key);
+    }
+}
+
+impl FromStr for GameState {
+    type Err = BoardError;
+
+    /// Parses 64 squares (0 - empty, 1 - black, 2 - white), optionally followed by B or W,
+    /// the player to move. Whitespace and ANSI color codes are ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars = strip_ansi(s)
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<Vec<_>>();
+
+        let (squares, turn) = match chars.split_last() {
+            Some((last, squares)) if squares.len() == BOARD_SQUARES => {
+                match last.to_ascii_uppercase() {
+                    'B' => (squares, Some(Player::Black)),
+                    'W' => (squares, Some(Player::White)),
+                    _ => return Err(BoardError::WrongLength(chars.len())),
+                }
+            }
+            _ if chars.len() == BOARD_SQUARES => (&chars[..], None),
+            _ => return Err(BoardError::WrongLength(chars.len())),
+        };
+
+        let mut black = bb::EMPTY;
+        let mut white = bb::EMPTY;
+        for (i, &c) in squares.iter().enumerate() {
+            match c {
+                '0' => (),
+                '1' => black |= 1 << i,
+                '2' => white |= 1 << i,
+                _ => return Err(BoardError::bad_character(i, c)),
+            }
+        }
+        Self::from_discs(black, white, turn)
+    }
+}
+
 impl Display for GameState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let moves = self.move_bb();
@@ -320,7 +458,7 @@ mod tests {
 
     #[quickcheck]
     fn random_state_returns_correct_move_number() -> bool {
-        let n = thread_rng().gen_range(1..=60);
+        let n = rng::with_rng(|rng| rng.gen_range(1..=60));
         GameState::random_state_between_inc(n, n).move_number() == n
     }
 
@@ -367,6 +505,96 @@ mod tests {
         assert_moves(&gs, &[p("B6"), p("C6"), p("D6"), p("E6"), p("F6")]);
     }
 
+    fn board_str(gs: &GameState) -> String {
+        let squares = (0..BOARD_SQUARES)
+            .map(|i| match gs.at(Position::from_index(i)) {
+                Square::Empty => '0',
+                Square::Placed(Player::Black) => '1',
+                Square::Placed(Player::White) => '2',
+            })
+            .collect::<String>();
+        let turn = match gs.turn() {
+            Player::Black => 'B',
+            Player::White => 'W',
+        };
+        format!("{squares}{turn}")
+    }
+
+    #[quickcheck]
+    fn from_str_reads_explicit_turn(gs: GameState) -> bool {
+        board_str(&gs).parse() == Ok(gs)
+    }
+
+    #[test]
+    fn from_str_ignores_formatting() {
+        let gs = GameState::othello_initial();
+        assert_eq!(
+            gs.to_string().lines().take(8).collect::<String>().parse(),
+            Ok(gs)
+        );
+    }
+
+    #[test]
+    fn from_str_reports_wrong_length() {
+        assert_eq!("0120".parse::<GameState>(), Err(BoardError::WrongLength(4)));
+        let board = "0".repeat(BOARD_SQUARES) + "0";
+        assert_eq!(board.parse::<GameState>(), Err(BoardError::WrongLength(65)));
+    }
+
+    #[test]
+    fn from_str_reports_bad_character_position() {
+        let mut board = "0".repeat(BOARD_SQUARES);
+        board.replace_range(19..20, "x");
+        assert_eq!(
+            board.parse::<GameState>(),
+            Err(BoardError::BadCharacter {
+                row: 2,
+                col: 3,
+                found: 'x'
+            })
+        );
+    }
+
+    #[test]
+    fn unverified_board_strings_skip_other_characters() {
+        let gs = GameState::othello_initial();
+        let board = board_str(&gs).replace('0', "0x");
+        assert_eq!(GameState::from_board_str_unverified(&board), Some(gs));
+        assert_eq!(GameState::from_board_str_unverified("0120"), None);
+    }
+
+    #[test]
+    fn from_discs_reports_overlapping_discs() {
+        let disc = bb::from_pos(p("C2"));
+        assert_eq!(
+            GameState::from_discs(disc, disc, None),
+            Err(BoardError::OverlappingDiscs { row: 1, col: 2 })
+        );
+    }
+
+    #[test]
+    fn from_str_requires_turn_after_passes() {
+        // White should move by the disc count, but only Black can move
+        let board = format!("12000000{0}{0}0001100000011000{0}{0}00000001", "00000000");
+        assert_eq!(board.parse::<GameState>(), Err(BoardError::UnknownTurn));
+        let gs = (board + "B").parse::<GameState>().unwrap();
+        assert_eq!(gs.turn(), Player::Black);
+    }
+
+    #[quickcheck]
+    fn zobrist_key_is_updated_incrementally(gs: GameState) -> bool {
+        gs.zobrist_key() == zobrist::key(gs.turn, gs.black, gs.white)
+    }
+
+    #[test]
+    fn zobrist_key_is_stable_between_runs() {
+        assert_eq!(GameState::reversi_initial().zobrist_key(), 0);
+        assert_eq!(
+            GameState::othello_initial().zobrist_key(),
+            0xCBB6_D952_89DF_4457
+        );
+    }
+
     #[test]
     #[should_panic]
     fn invalid_moves_panic() {
@@ -1,12 +1,22 @@
 pub mod bitboard;
+mod board_error;
 mod game_state;
 mod outcome;
+pub mod perft;
 mod player;
 mod position;
+pub mod record;
 mod square;
+mod symmetry;
+pub mod zobrist;
 
+pub use board_error::BoardError;
 pub use game_state::GameState;
 pub use outcome::Outcome;
 pub use player::Player;
 pub use position::{Position, BOARD_SIDE, BOARD_SQUARES};
 pub use square::Square;
+pub use symmetry::Symmetry;
+
+#[cfg(test)]
+pub(crate) use position::p;
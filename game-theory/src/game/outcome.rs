@@ -3,7 +3,7 @@ use colored::Colorize;
 use std::fmt::{self, Display};
 
 #[must_use]
-#[derive(PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Outcome {
     Winner(Player),
     Draw,
@@ -0,0 +1,49 @@
+use super::GameState;
+use std::ops::AddAssign;
+
+/// Node counts of the game tree, where a forced pass counts as a ply.
+#[must_use]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PerftCounts {
+    /// Positions at the given depth, games finished earlier are also counted.
+    pub leaves: u64,
+    /// Passes made within the given depth.
+    pub passes: u64,
+    /// Finished games, either at the given depth or earlier.
+    pub finished: u64,
+}
+
+impl AddAssign for PerftCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.leaves += other.leaves;
+        self.passes += other.passes;
+        self.finished += other.finished;
+    }
+}
+
+/// Counts all move sequences of the given length, used to verify move generation.
+pub fn perft(gs: &GameState, depth: u32) -> PerftCounts {
+    let mut counts = PerftCounts::default();
+    if gs.outcome().is_some() {
+        // Finished games are leaves at any depth, as in the commonly published numbers
+        counts.finished = 1;
+        counts.leaves = 1;
+        return counts;
+    }
+    if depth == 0 {
+        counts.leaves = 1;
+        return counts;
+    }
+
+    for position in gs.moves() {
+        let child = gs.make_move(position);
+        let mut depth = depth - 1;
+        if child.turn() == gs.turn() && child.outcome().is_none() && depth > 0 {
+            // Opponent had to pass, which uses up a ply
+            counts.passes += 1;
+            depth -= 1;
+        }
+        counts += perft(&child, depth);
+    }
+    counts
+}
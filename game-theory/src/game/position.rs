@@ -44,7 +44,7 @@ impl Position {
     }
 
     pub fn neighbours(&self) -> impl Iterator<Item = Self> {
-        bb::positions(bb::neighbours(bb::from_pos(*self))).into_iter()
+        bb::bits(bb::neighbours(bb::from_pos(*self)))
     }
 }
 
@@ -0,0 +1,605 @@
+use super::{bitboard as bb, GameState, Outcome, Player, Position, BOARD_SIDE, BOARD_SQUARES};
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io::{self, Write},
+    time::Duration,
+};
+
+const PASS_NOTATION: &str = "PA";
+/// Pass notations accepted when parsing, different programs use different ones.
+const PASS_ALIASES: [&str; 3] = [PASS_NOTATION, "PS", "--"];
+
+const GGF_EMPTY: char = '-';
+const GGF_BLACK: char = '*';
+const GGF_WHITE: char = 'O';
+
+/// Single entry of a game transcript.
+#[must_use]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ply {
+    Move(Position),
+    Pass,
+}
+
+impl Display for Ply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ply::Move(position) => position.fmt(f),
+            Ply::Pass => write!(f, "{PASS_NOTATION}"),
+        }
+    }
+}
+
+/// Reason why a game record could not be loaded, plies are numbered from 1.
+#[must_use]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RecordError {
+    /// Token is neither a position nor a pass.
+    InvalidToken { ply: usize, token: String },
+    /// Position is not a valid move for the player to move.
+    IllegalMove {
+        ply: usize,
+        player: Player,
+        position: Position,
+    },
+    /// Player passed, even though they had a valid move.
+    IllegalPass { ply: usize, player: Player },
+    /// Ply was recorded after the end of the game.
+    GameOver { ply: usize },
+    /// Move was recorded for the player, who was not the one to move.
+    WrongPlayer {
+        ply: usize,
+        expected: Player,
+        found: Player,
+    },
+    /// Text is not a valid GGF game.
+    MalformedGgf(String),
+}
+
+impl Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::InvalidToken { ply, token } => {
+                write!(f, "invalid token \"{token}\" at ply {ply}")
+            }
+            RecordError::IllegalMove {
+                ply,
+                player,
+                position,
+            } => write!(f, "illegal move {position} by {player:?} at ply {ply}"),
+            RecordError::IllegalPass { ply, player } => {
+                write!(f, "illegal pass by {player:?} at ply {ply}")
+            }
+            RecordError::GameOver { ply } => write!(f, "game is already over at ply {ply}"),
+            RecordError::WrongPlayer {
+                ply,
+                expected,
+                found,
+            } => write!(f, "{found:?} played at ply {ply}, expected {expected:?}"),
+            RecordError::MalformedGgf(reason) => write!(f, "malformed GGF: {reason}"),
+        }
+    }
+}
+
+impl Error for RecordError {}
+
+fn malformed(reason: &str) -> RecordError {
+    RecordError::MalformedGgf(String::from(reason))
+}
+
+#[must_use]
+fn parse_ply(token: &str) -> Option<Ply> {
+    let token = token.trim().to_uppercase();
+    if PASS_ALIASES.contains(&token.as_str()) {
+        Some(Ply::Pass)
+    } else {
+        Position::from(&token).map(Ply::Move)
+    }
+}
+
+/// Validated sequence of moves played from a starting position.
+#[must_use]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameRecord {
+    pub black_name: Option<String>,
+    pub white_name: Option<String>,
+    start: GameState,
+    moves: Vec<Position>,
+    /// Thinking time of every move, if known.
+    times: Vec<Option<Duration>>,
+    end: GameState,
+    /// Whether the last move forced the opponent to pass.
+    pending_pass: bool,
+    /// Number of plies, including the passes which weren't recorded explicitly.
+    ply_count: usize,
+}
+
+impl GameRecord {
+    pub fn new(start: GameState) -> Self {
+        Self {
+            black_name: None,
+            white_name: None,
+            end: start.clone(),
+            start,
+            moves: Vec::new(),
+            times: Vec::new(),
+            pending_pass: false,
+            ply_count: 0,
+        }
+    }
+
+    pub const fn start(&self) -> &GameState {
+        &self.start
+    }
+
+    /// State after the last recorded move.
+    pub const fn end(&self) -> &GameState {
+        &self.end
+    }
+
+    pub fn moves(&self) -> &[Position] {
+        &self.moves
+    }
+
+    /// Thinking time of every move, in the same order as the moves.
+    pub fn times(&self) -> &[Option<Duration>] {
+        &self.times
+    }
+
+    /// Final result, if the game is over.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.end.outcome()
+    }
+
+    /// Returns the recorded moves together with the passes and the players who made them.
+    #[must_use]
+    pub fn plies(&self) -> Vec<(Player, Ply)> {
+        let mut plies = Vec::with_capacity(self.moves.len());
+        let mut gs = self.start.clone();
+        for &position in &self.moves {
+            let mover = gs.turn();
+            gs = gs.make_move(position);
+            plies.push((mover, Ply::Move(position)));
+            if gs.turn() == mover && gs.outcome().is_none() {
+                plies.push((mover.opponent(), Ply::Pass));
+            }
+        }
+        plies
+    }
+
+    /// Validates and records the ply, passes may also be skipped, as they are forced.
+    pub fn play(&mut self, ply: Ply) -> Result<(), RecordError> {
+        let index = self.ply_count + 1;
+        self.apply(index, ply)
+    }
+
+    /// Validates and records the move together with the time spent on it.
+    pub fn play_timed(&mut self, position: Position, time: Duration) -> Result<(), RecordError> {
+        self.play(Ply::Move(position))?;
+        *self.times.last_mut().unwrap() = Some(time);
+        Ok(())
+    }
+
+    fn apply(&mut self, index: usize, ply: Ply) -> Result<(), RecordError> {
+        if self.end.outcome().is_some() {
+            return Err(RecordError::GameOver { ply: index });
+        }
+
+        match ply {
+            Ply::Pass if self.pending_pass => self.pending_pass = false,
+            Ply::Pass => {
+                return Err(RecordError::IllegalPass {
+                    ply: index,
+                    player: self.end.turn(),
+                })
+            }
+            Ply::Move(position) => {
+                let player = self.end.turn();
+                if !bb::has(self.end.move_bb(), position) {
+                    return Err(RecordError::IllegalMove {
+                        ply: index,
+                        player,
+                        position,
+                    });
+                }
+                self.end = self.end.make_move(position);
+                self.moves.push(position);
+                self.times.push(None);
+                self.pending_pass = self.end.turn() == player && self.end.outcome().is_none();
+                // Forced passes are counted right away, as they may never be recorded
+                self.ply_count += 1 + usize::from(self.pending_pass);
+            }
+        }
+        Ok(())
+    }
+
+    /// Player expected to make the given ply.
+    const fn mover(&self, ply: Ply) -> Player {
+        match ply {
+            Ply::Pass if self.pending_pass => self.end.turn().opponent(),
+            _ => self.end.turn(),
+        }
+    }
+
+    /// Parses a transcript like "F5D6C3", whitespace is ignored and passes are optional.
+    pub fn from_transcript(start: GameState, transcript: &str) -> Result<Self, RecordError> {
+        let chars = transcript
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<Vec<_>>();
+
+        let mut record = Self::new(start);
+        for (i, chunk) in chars.chunks(2).enumerate() {
+            let token = chunk.iter().collect::<String>();
+            let ply = parse_ply(&token).ok_or(RecordError::InvalidToken { ply: i + 1, token })?;
+            record.apply(i + 1, ply)?;
+        }
+        Ok(record)
+    }
+
+    /// Writes the transcript with every pass included explicitly.
+    #[must_use]
+    pub fn transcript(&self) -> String {
+        self.plies()
+            .into_iter()
+            .map(|(_, ply)| ply.to_string())
+            .collect()
+    }
+
+    /// Parses a single game in the Generic Game Format used by online Othello servers.
+    pub fn from_ggf(ggf: &str) -> Result<Self, RecordError> {
+        let body = ggf
+            .trim()
+            .strip_prefix("(;")
+            .and_then(|body| body.strip_suffix(";)"))
+            .ok_or_else(|| malformed("game has to be enclosed in \"(;\" and \";)\""))?;
+
+        let mut tags = Vec::new();
+        let mut rest = body.trim_start();
+        while !rest.is_empty() {
+            let (key, value) = rest
+                .split_once('[')
+                .ok_or_else(|| malformed("tag without a value"))?;
+            let (value, next) =
+                split_ggf_value(value).ok_or_else(|| malformed("unterminated tag value"))?;
+            tags.push((key.trim(), value));
+            rest = next.trim_start();
+        }
+
+        let start = match tags.iter().find(|(key, _)| *key == "BO") {
+            Some((_, board)) => parse_ggf_board(board)?,
+            None => GameState::othello_initial(),
+        };
+        let mut record = Self::new(start);
+        let mut index = 0;
+        for (key, value) in tags {
+            let value = value.as_str();
+            match key {
+                "GM" if !value.eq_ignore_ascii_case("othello") => {
+                    return Err(malformed("game type is not Othello"))
+                }
+                "PB" => record.black_name = Some(String::from(value)),
+                "PW" => record.white_name = Some(String::from(value)),
+                "B" | "W" => {
+                    index += 1;
+                    // Moves may be followed by an evaluation and time, like "d3/1.50/2.3"
+                    let mut parts = value.split('/');
+                    let token = parts.next().unwrap_or_default();
+                    let time = parts.nth(1).and_then(parse_ggf_time);
+                    let ply = parse_ply(token).ok_or_else(|| RecordError::InvalidToken {
+                        ply: index,
+                        token: String::from(token),
+                    })?;
+                    let found = if key == "B" {
+                        Player::Black
+                    } else {
+                        Player::White
+                    };
+                    let expected = record.mover(ply);
+                    if found != expected {
+                        return Err(RecordError::WrongPlayer {
+                            ply: index,
+                            expected,
+                            found,
+                        });
+                    }
+                    record.apply(index, ply)?;
+                    if let (Ply::Move(_), Some(time)) = (ply, time) {
+                        *record.times.last_mut().unwrap() = Some(time);
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(record)
+    }
+
+    /// Writes the game in the Generic Game Format, passes are written explicitly.
+    #[must_use]
+    pub fn to_ggf(&self) -> String {
+        let mut ggf = String::from("(;GM[Othello]PC[uni-sem-6-ai]");
+        if let Some(name) = &self.black_name {
+            ggf += &format!("PB[{}]", escape_ggf(name));
+        }
+        if let Some(name) = &self.white_name {
+            ggf += &format!("PW[{}]", escape_ggf(name));
+        }
+        if self.end.outcome().is_some() {
+            let margin =
+                self.end.score_of(Player::Black) as f64 - self.end.score_of(Player::White) as f64;
+            ggf += &format!("RE[{margin:+.3}]");
+        }
+        ggf += &format!("TY[8]BO[{}]", ggf_board(&self.start));
+        let mut times = self.times.iter();
+        for (player, ply) in self.plies() {
+            let key = match player {
+                Player::Black => "B",
+                Player::White => "W",
+            };
+            let time = match ply {
+                Ply::Move(_) => times.next().copied().flatten(),
+                Ply::Pass => None,
+            };
+            match time {
+                Some(time) => ggf += &format!("{key}[{ply}//{:.3}]", time.as_secs_f64()),
+                None => ggf += &format!("{key}[{ply}]"),
+            }
+        }
+        ggf + ";)"
+    }
+}
+
+/// Writes the games in the Generic Game Format, one game per line.
+pub fn write_ggf<'a>(
+    records: impl IntoIterator<Item = &'a GameRecord>,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    for record in records {
+        writeln!(writer, "{}", record.to_ggf())?;
+    }
+    Ok(())
+}
+
+/// Parses games in the Generic Game Format written one per line, empty lines are skipped.
+pub fn read_ggf(text: &str) -> Result<Vec<GameRecord>, RecordError> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(GameRecord::from_ggf)
+        .collect()
+}
+
+/// Escapes `]` and `\` in a tag value with a backslash.
+#[must_use]
+fn escape_ggf(value: &str) -> String {
+    value.replace('\\', "\\\\").replace(']', "\\]")
+}
+
+/// Splits off a tag value ended by the first unescaped `]`, removing the escapes.
+fn split_ggf_value(text: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => value.push(chars.next()?.1),
+            ']' => return Some((value, &text[i + 1..])),
+            _ => value.push(c),
+        }
+    }
+    None
+}
+
+/// Parses a move time given in seconds, rounded to microseconds.
+fn parse_ggf_time(text: &str) -> Option<Duration> {
+    let seconds: f64 = text.trim().parse().ok()?;
+    (seconds.is_finite() && seconds >= 0.)
+        .then(|| Duration::from_micros((seconds * 1e6).round() as u64))
+}
+
+#[must_use]
+fn ggf_board(gs: &GameState) -> String {
+    let mut board = format!("{BOARD_SIDE}");
+    for row in 0..BOARD_SIDE {
+        board.push(' ');
+        for col in 0..BOARD_SIDE {
+            let position = Position::from_index(row * BOARD_SIDE + col);
+            board.push(if bb::has(gs.bb_of(Player::Black), position) {
+                GGF_BLACK
+            } else if bb::has(gs.bb_of(Player::White), position) {
+                GGF_WHITE
+            } else {
+                GGF_EMPTY
+            });
+        }
+    }
+    board.push(' ');
+    board.push(match gs.turn() {
+        Player::Black => GGF_BLACK,
+        Player::White => GGF_WHITE,
+    });
+    board
+}
+
+fn parse_ggf_board(board: &str) -> Result<GameState, RecordError> {
+    let board = board
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+    let squares = board
+        .strip_prefix(&BOARD_SIDE.to_string())
+        .ok_or_else(|| malformed("only 8x8 boards are supported"))?;
+    let squares = squares.chars().collect::<Vec<_>>();
+    if squares.len() != BOARD_SQUARES + 1 {
+        return Err(malformed(
+            "board has to list every square and the player to move",
+        ));
+    }
+
+    let mut black = bb::EMPTY;
+    let mut white = bb::EMPTY;
+    for (i, square) in squares[..BOARD_SQUARES].iter().enumerate() {
+        match square.to_ascii_uppercase() {
+            GGF_EMPTY => (),
+            GGF_BLACK => black |= 1 << i,
+            GGF_WHITE => white |= 1 << i,
+            _ => return Err(malformed("invalid board square")),
+        }
+    }
+    let turn = match squares[BOARD_SQUARES].to_ascii_uppercase() {
+        GGF_BLACK => Player::Black,
+        GGF_WHITE => Player::White,
+        _ => return Err(malformed("invalid player to move")),
+    };
+    Ok(GameState::from_bitboards(turn, black, white))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ai::{RandomMove, Strategy},
+        game::p,
+    };
+    use quickcheck_macros::quickcheck;
+
+    fn random_record() -> GameRecord {
+        let mut record = GameRecord::new(GameState::othello_initial());
+        while record.end().outcome().is_none() {
+            let position = RandomMove::new().decide(record.end());
+            record.play(Ply::Move(position)).unwrap();
+        }
+        record
+    }
+
+    #[quickcheck]
+    fn transcript_round_trips() -> bool {
+        let record = random_record();
+        let parsed =
+            GameRecord::from_transcript(GameState::othello_initial(), &record.transcript());
+        parsed == Ok(record)
+    }
+
+    #[quickcheck]
+    fn passes_in_transcripts_are_optional() -> bool {
+        let record = random_record();
+        let without_passes = record.transcript().replace(PASS_NOTATION, "");
+        let parsed = GameRecord::from_transcript(GameState::othello_initial(), &without_passes);
+        parsed.map(|r| r.transcript()) == Ok(record.transcript())
+    }
+
+    #[quickcheck]
+    fn ggf_round_trips() -> bool {
+        let mut record = random_record();
+        record.black_name = Some(String::from("αβ(KORMAN, 4)"));
+        GameRecord::from_ggf(&record.to_ggf()) == Ok(record)
+    }
+
+    #[quickcheck]
+    fn plies_are_counted_as_they_are_played() -> bool {
+        let mut record = random_record();
+        let ply = record.plies().len() + 1;
+        record.play(Ply::Pass) == Err(RecordError::GameOver { ply })
+    }
+
+    #[test]
+    fn ggf_names_are_escaped() {
+        let mut record = GameRecord::new(GameState::othello_initial());
+        record.black_name = Some(String::from(r"Mix(1-20: a]b; c\d)"));
+        record.white_name = Some(String::from("]"));
+        record.play(Ply::Move(p("F5"))).unwrap();
+        let ggf = record.to_ggf();
+        assert!(ggf.contains(r"PB[Mix(1-20: a\]b; c\\d)]PW[\]]"), "{ggf}");
+        assert_eq!(GameRecord::from_ggf(&ggf), Ok(record));
+    }
+
+    #[quickcheck]
+    fn move_times_round_trip_through_ggf() -> bool {
+        let mut record = GameRecord::new(GameState::othello_initial());
+        let mut millis = 0;
+        while record.end().outcome().is_none() {
+            millis += 7;
+            let position = RandomMove::new().decide(record.end());
+            let time = Duration::from_millis(millis);
+            record.play_timed(position, time).unwrap();
+        }
+        let mut written = Vec::new();
+        write_ggf([&record, &record], &mut written).unwrap();
+        let read = read_ggf(&String::from_utf8(written).unwrap());
+        read == Ok(vec![record.clone(), record])
+    }
+
+    #[test]
+    fn transcripts_ignore_case_and_whitespace() {
+        let record =
+            GameRecord::from_transcript(GameState::othello_initial(), "f5 d6\nC3").unwrap();
+        assert_eq!(record.moves(), &[p("F5"), p("D6"), p("C3")]);
+        assert_eq!(record.transcript(), "F5D6C3");
+    }
+
+    #[test]
+    fn illegal_moves_are_reported_with_their_position() {
+        let result = GameRecord::from_transcript(GameState::othello_initial(), "F5D6C3A1");
+        assert_eq!(
+            result,
+            Err(RecordError::IllegalMove {
+                ply: 4,
+                player: Player::White,
+                position: p("A1"),
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_tokens_and_passes_are_reported() {
+        let gs = GameState::othello_initial();
+        assert_eq!(
+            GameRecord::from_transcript(gs.clone(), "F5PA"),
+            Err(RecordError::IllegalPass {
+                ply: 2,
+                player: Player::White,
+            })
+        );
+        assert_eq!(
+            GameRecord::from_transcript(gs.clone(), "F5D6Z9"),
+            Err(RecordError::InvalidToken {
+                ply: 3,
+                token: String::from("Z9"),
+            })
+        );
+        assert_eq!(
+            GameRecord::from_transcript(gs, "E6F4E3F6G5D6E7F5C5D3"),
+            Err(RecordError::GameOver { ply: 10 })
+        );
+    }
+
+    #[test]
+    fn ggf_moves_may_contain_evaluations_and_times() {
+        let ggf = "(;GM[Othello]PC[NBoard]PB[Alice]PW[Bob]TY[8]\
+            BO[8 -------- -------- -------- ---O*--- ---*O--- -------- -------- -------- *]\
+            B[f5//1.25]W[d6/-0.50/2.1]B[c3];)";
+        let record = GameRecord::from_ggf(ggf).unwrap();
+        assert_eq!(record.black_name.as_deref(), Some("Alice"));
+        assert_eq!(record.white_name.as_deref(), Some("Bob"));
+        assert_eq!(record.start(), &GameState::othello_initial());
+        assert_eq!(record.moves(), &[p("F5"), p("D6"), p("C3")]);
+        let times = [Duration::from_millis(1250), Duration::from_millis(2100)];
+        assert_eq!(record.times(), &[Some(times[0]), Some(times[1]), None]);
+        assert!(record.to_ggf().contains("B[F5//1.250]W[D6//2.100]B[C3]"));
+    }
+
+    #[test]
+    fn ggf_errors_are_reported() {
+        assert!(matches!(
+            GameRecord::from_ggf("GM[Othello]B[f5]"),
+            Err(RecordError::MalformedGgf(_))
+        ));
+        assert_eq!(
+            GameRecord::from_ggf("(;GM[Othello]B[f5]B[d6];)"),
+            Err(RecordError::WrongPlayer {
+                ply: 2,
+                expected: Player::White,
+                found: Player::Black,
+            })
+        );
+    }
+}
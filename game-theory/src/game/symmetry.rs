@@ -0,0 +1,127 @@
+use super::{
+    bitboard::{self as bb, Bitboard},
+    Position,
+};
+
+/// One of the 8 symmetries of the square board (the dihedral group D4).
+#[must_use]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipVertical,
+    FlipHorizontal,
+    FlipDiagonal,
+    FlipAntiDiagonal,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipVertical,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipDiagonal,
+        Symmetry::FlipAntiDiagonal,
+    ];
+
+    #[must_use]
+    pub const fn apply(&self, bitboard: Bitboard) -> Bitboard {
+        match self {
+            Symmetry::Identity => bitboard,
+            Symmetry::Rotate90 => bb::rotate_90(bitboard),
+            Symmetry::Rotate180 => bb::rotate_180(bitboard),
+            Symmetry::Rotate270 => bb::rotate_270(bitboard),
+            Symmetry::FlipVertical => bb::flip_vertical(bitboard),
+            Symmetry::FlipHorizontal => bb::flip_horizontal(bitboard),
+            Symmetry::FlipDiagonal => bb::flip_diagonal(bitboard),
+            Symmetry::FlipAntiDiagonal => bb::flip_anti_diagonal(bitboard),
+        }
+    }
+
+    pub const fn inverse(&self) -> Self {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            // Remaining symmetries are their own inverses
+            _ => *self,
+        }
+    }
+
+    pub fn apply_position(&self, position: Position) -> Position {
+        Position::from_index(self.apply(bb::from_pos(position)).trailing_zeros() as usize)
+    }
+
+    /// Maps the position on the transformed board back to the original board.
+    pub fn revert_position(&self, position: Position) -> Position {
+        self.inverse().apply_position(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{p, GameState};
+    use quickcheck::Arbitrary;
+    use quickcheck_macros::quickcheck;
+
+    impl Arbitrary for Symmetry {
+        fn arbitrary(gen: &mut quickcheck::Gen) -> Self {
+            *gen.choose(&Symmetry::ALL).unwrap()
+        }
+    }
+
+    #[test]
+    fn symmetries_move_corners_as_expected() {
+        let corner = |symmetry: Symmetry| symmetry.apply_position(p("A1")).to_string();
+        assert_eq!(corner(Symmetry::Identity), "A1");
+        assert_eq!(corner(Symmetry::Rotate90), "H1");
+        assert_eq!(corner(Symmetry::Rotate180), "H8");
+        assert_eq!(corner(Symmetry::Rotate270), "A8");
+        assert_eq!(corner(Symmetry::FlipVertical), "A8");
+        assert_eq!(corner(Symmetry::FlipHorizontal), "H1");
+        assert_eq!(corner(Symmetry::FlipDiagonal), "A1");
+        assert_eq!(corner(Symmetry::FlipAntiDiagonal), "H8");
+        assert_eq!(Symmetry::FlipDiagonal.apply_position(p("B1")), p("A2"));
+        assert_eq!(Symmetry::FlipAntiDiagonal.apply_position(p("B1")), p("H7"));
+    }
+
+    #[quickcheck]
+    fn revert_position_undoes_apply_position(symmetry: Symmetry, position: Position) -> bool {
+        symmetry.revert_position(symmetry.apply_position(position)) == position
+    }
+
+    #[quickcheck]
+    fn symmetries_keep_the_disc_count(symmetry: Symmetry, gs: GameState) -> bool {
+        symmetry.apply(gs.occupied_bb()).count_ones() == gs.occupied_bb().count_ones()
+    }
+
+    #[quickcheck]
+    fn symmetries_map_valid_moves(symmetry: Symmetry, gs: GameState) -> bool {
+        symmetry.apply(gs.move_bb()) == gs.transformed(symmetry).move_bb()
+    }
+
+    #[quickcheck]
+    fn symmetric_states_have_the_same_canonical_form(symmetry: Symmetry, gs: GameState) -> bool {
+        gs.canonical().0 == gs.transformed(symmetry).canonical().0
+    }
+
+    #[quickcheck]
+    fn canonical_returns_the_symmetry_used(gs: GameState) -> bool {
+        let (canonical, symmetry) = gs.canonical();
+        gs.transformed(symmetry) == canonical
+    }
+
+    #[quickcheck]
+    fn moves_map_back_to_the_original_board(gs: GameState) -> bool {
+        let (canonical, symmetry) = gs.canonical();
+        canonical
+            .moves()
+            .into_iter()
+            .all(|position| bb::has(gs.move_bb(), symmetry.revert_position(position)))
+    }
+}
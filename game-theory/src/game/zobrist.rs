@@ -0,0 +1,87 @@
+use super::{bitboard::Bitboard, Player, BOARD_SQUARES};
+use std::hash::{BuildHasherDefault, Hasher};
+
+/// Fixed seed, so that keys stay the same between runs.
+const SEED: u64 = 0x5EED_0F07_E110;
+
+/// SplitMix64 step, returns the next state and the generated number.
+const fn split_mix(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (state, z ^ (z >> 31))
+}
+
+const fn generate_keys() -> ([[u64; BOARD_SQUARES]; 2], u64) {
+    let mut squares = [[0; BOARD_SQUARES]; 2];
+    let mut state = SEED;
+    let mut player = 0;
+    while player < 2 {
+        let mut i = 0;
+        while i < BOARD_SQUARES {
+            let (next, key) = split_mix(state);
+            squares[player][i] = key;
+            state = next;
+            i += 1;
+        }
+        player += 1;
+    }
+    let (_, side) = split_mix(state);
+    (squares, side)
+}
+
+const KEYS: ([[u64; BOARD_SQUARES]; 2], u64) = generate_keys();
+const SQUARE_KEYS: [[u64; BOARD_SQUARES]; 2] = KEYS.0;
+/// Included in the key when White is to move.
+pub(super) const WHITE_TO_MOVE: u64 = KEYS.1;
+
+/// Combined keys of all discs of the player.
+#[must_use]
+pub(super) const fn discs_key(player: Player, mut discs: Bitboard) -> u64 {
+    let keys = &SQUARE_KEYS[player as usize - 1];
+    let mut key = 0;
+    while discs != 0 {
+        key ^= keys[discs.trailing_zeros() as usize];
+        discs &= discs - 1;
+    }
+    key
+}
+
+#[must_use]
+pub(super) const fn key(turn: Player, black: Bitboard, white: Bitboard) -> u64 {
+    let side = match turn {
+        Player::Black => 0,
+        Player::White => WHITE_TO_MOVE,
+    };
+    discs_key(Player::Black, black) ^ discs_key(Player::White, white) ^ side
+}
+
+/// Hasher which passes Zobrist keys through, other data is mixed with FNV-1a.
+#[must_use]
+#[derive(Clone, Copy, Debug)]
+pub struct ZobristHasher(u64);
+
+impl Default for ZobristHasher {
+    fn default() -> Self {
+        Self(0xCBF2_9CE4_8422_2325)
+    }
+}
+
+impl Hasher for ZobristHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0100_0000_01B3);
+        }
+    }
+
+    fn write_u64(&mut self, key: u64) {
+        self.0 ^= key;
+    }
+}
+
+pub type ZobristBuildHasher = BuildHasherDefault<ZobristHasher>;
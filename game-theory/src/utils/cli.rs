@@ -1,7 +1,19 @@
-use crate::ai::{AlphaBeta, Heuristic, Minimax, TreeVisitingStrategy};
+use crate::ai::{
+    heuristics::Phase,
+    weights::{parse_weights, WeightsError},
+    AlphaBeta, Heuristic, IterativeDeepening, Minimax, TreeVisitingStrategy,
+};
 use clap::ValueEnum;
 use colored::{ColoredString, Colorize};
 use once_cell::sync::Lazy;
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    fs,
+    ops::RangeInclusive,
+    path::Path,
+    time::Duration,
+};
 
 pub static INFO: Lazy<ColoredString> = Lazy::new(|| "INFO".bright_blue());
 pub static OK: Lazy<ColoredString> = Lazy::new(|| "OK".bright_green());
@@ -59,6 +71,239 @@ impl From<HeuristicArg> for Heuristic {
     }
 }
 
+/// Move numbers of a whole game, used for phases without a range.
+const ALL_MOVES: (i32, i32) = (1, 60);
+
+/// Reason why a heuristic expression could not be parsed.
+#[must_use]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HeuristicError {
+    /// Expression, phase or term is empty.
+    Empty,
+    /// Name isn't one of the available heuristics, nor a path of a file.
+    UnknownName(String),
+    /// File with a weight matrix could not be read, the path and the reason are given.
+    UnreadableFile(String, String),
+    /// File doesn't contain a valid weight matrix.
+    InvalidWeights(String, WeightsError),
+    /// Weight isn't a positive number.
+    InvalidWeight(String),
+    /// Range isn't given as `first-last` within the move numbers of a game.
+    InvalidRange(String),
+    /// Move number is covered by two ranges.
+    OverlappingRanges(i32),
+    /// Move number isn't covered by any range, and there is no phase without a range.
+    UncoveredMove(i32),
+    /// More than one phase is given without a range.
+    DuplicateDefault,
+}
+
+impl Display for HeuristicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeuristicError::Empty => write!(f, "empty heuristic expression"),
+            HeuristicError::UnknownName(name) => write!(f, "unknown heuristic '{name}'"),
+            HeuristicError::UnreadableFile(path, error) => {
+                write!(f, "weights file '{path}' can't be read: {error}")
+            }
+            HeuristicError::InvalidWeights(path, error) => {
+                write!(f, "invalid weights in '{path}': {error}")
+            }
+            HeuristicError::InvalidWeight(weight) => {
+                write!(f, "weight '{weight}' isn't a positive number")
+            }
+            HeuristicError::InvalidRange(range) => write!(
+                f,
+                "range '{range}' isn't given as first-last within {}-{}",
+                ALL_MOVES.0, ALL_MOVES.1
+            ),
+            HeuristicError::OverlappingRanges(n) => write!(f, "move {n} is in two ranges"),
+            HeuristicError::UncoveredMove(n) => write!(f, "move {n} isn't in any range"),
+            HeuristicError::DuplicateDefault => write!(f, "only one phase can be without a range"),
+        }
+    }
+}
+
+impl Error for HeuristicError {}
+
+/// Parses a heuristic name, or an expression combining them with weighted averages.
+///
+/// Path of a file with a weight matrix can be used in place of any name.
+/// An expression is a sum of weighted names, such as `0.8*corn-own + 0.2*cur-mob`,
+/// and a missing weight means 1. Phases separated with `;` may start with a range of
+/// move numbers, as in `1-20: cur-mob; 21-60: stab`. A phase without a range is used
+/// for all moves not covered by the other phases.
+///
+/// Paths aren't quoted, so within an expression they can't contain `+`, `*`, `:` or `;`,
+/// and can't start or end with whitespace.
+pub fn parse_heuristic(text: &str) -> Result<Heuristic, HeuristicError> {
+    match parse_name(text.trim()) {
+        Err(HeuristicError::UnknownName(_)) => {}
+        result => return result,
+    }
+
+    let mut phases = Vec::new();
+    let mut default = None;
+    for phase in text.split(';') {
+        let (moves, sum) = match phase.split_once(':') {
+            Some((range, sum)) => (Some(parse_range(range)?), sum),
+            None => (None, phase),
+        };
+        let factors = sum.split('+').map(parse_term).collect::<Result<_, _>>()?;
+        match moves {
+            Some(moves) => phases.push(Phase { moves, factors }),
+            None if default.is_none() => {
+                let moves = ALL_MOVES.0..=ALL_MOVES.1;
+                default = Some(Phase { moves, factors });
+            }
+            None => return Err(HeuristicError::DuplicateDefault),
+        }
+    }
+
+    for n in ALL_MOVES.0..=ALL_MOVES.1 {
+        match phases.iter().filter(|p| p.moves.contains(&n)).count() {
+            0 if default.is_none() => return Err(HeuristicError::UncoveredMove(n)),
+            0 | 1 => {}
+            _ => return Err(HeuristicError::OverlappingRanges(n)),
+        }
+    }
+    // Phases are tried in order, so the default one has to be the last
+    phases.extend(default);
+    Ok(Heuristic::Composite(phases.into_boxed_slice()))
+}
+
+fn parse_range(text: &str) -> Result<RangeInclusive<i32>, HeuristicError> {
+    let invalid = || HeuristicError::InvalidRange(text.trim().to_string());
+    let (first, last) = text.split_once('-').ok_or_else(invalid)?;
+    let first: i32 = first.trim().parse().map_err(|_| invalid())?;
+    let last: i32 = last.trim().parse().map_err(|_| invalid())?;
+    if ALL_MOVES.0 <= first && first <= last && last <= ALL_MOVES.1 {
+        Ok(first..=last)
+    } else {
+        Err(invalid())
+    }
+}
+
+fn parse_term(text: &str) -> Result<(f64, Heuristic), HeuristicError> {
+    let (weight, name) = match text.split_once('*') {
+        Some((weight, name)) => {
+            let invalid = || HeuristicError::InvalidWeight(weight.trim().to_string());
+            let weight: f64 = weight.trim().parse().map_err(|_| invalid())?;
+            if !(weight.is_finite() && weight > 0.) {
+                return Err(invalid());
+            }
+            (weight, name.trim())
+        }
+        None => (1., text.trim()),
+    };
+    if name.is_empty() {
+        return Err(HeuristicError::Empty);
+    }
+    Ok((weight, parse_name(name)?))
+}
+
+/// Finds the heuristic with the given name, or loads the weight matrix from the given path.
+fn parse_name(name: &str) -> Result<Heuristic, HeuristicError> {
+    if let Ok(arg) = HeuristicArg::from_str(name, true) {
+        return Ok(arg.into());
+    }
+    let path = Path::new(name);
+    if !path.is_file() {
+        return Err(HeuristicError::UnknownName(name.to_string()));
+    }
+
+    let text = fs::read_to_string(path)
+        .map_err(|error| HeuristicError::UnreadableFile(name.to_string(), error.to_string()))?;
+    let weights = parse_weights(&text)
+        .map_err(|error| HeuristicError::InvalidWeights(name.to_string(), error))?;
+    let stem = path.file_stem().unwrap_or(path.as_os_str());
+    let name = stem.to_string_lossy().into_owned();
+    Ok(Heuristic::LoadedWeighted(name, Box::new(weights)))
+}
+
+/// Deepest search allowed for strategies given on the command line.
+const MAX_DEPTH: u32 = 10;
+
+/// How far a strategy given on the command line searches.
+#[must_use]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchLimit {
+    Depth(u32),
+    TimeMs(u64),
+}
+
+/// Strategy given on the command line, built once per use as strategies aren't `Clone`.
+#[must_use]
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrategySpec {
+    pub heuristic: Heuristic,
+    pub limit: SearchLimit,
+}
+
+impl StrategySpec {
+    pub fn build(&self, fast_wins: bool) -> Box<dyn TreeVisitingStrategy> {
+        match self.limit {
+            SearchLimit::Depth(depth) => {
+                build_strategy(self.heuristic.clone(), depth, false, fast_wins)
+            }
+            SearchLimit::TimeMs(time_ms) => {
+                build_timed_strategy(self.heuristic.clone(), time_ms, fast_wins)
+            }
+        }
+    }
+}
+
+/// Reason why a strategy could not be parsed.
+#[must_use]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StrategyError {
+    /// Strategy isn't given as `heuristic@limit`.
+    MissingLimit,
+    /// Limit is neither a depth in range 1..=10 nor a positive time like `100ms`.
+    InvalidLimit(String),
+    InvalidHeuristic(HeuristicError),
+}
+
+impl Display for StrategyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StrategyError::MissingLimit => {
+                write!(
+                    f,
+                    "strategy has to be given as heuristic@depth or heuristic@<time>ms"
+                )
+            }
+            StrategyError::InvalidLimit(limit) => write!(
+                f,
+                "limit '{limit}' is neither a depth in range 1..={MAX_DEPTH} nor a time like 100ms"
+            ),
+            StrategyError::InvalidHeuristic(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for StrategyError {}
+
+/// Parses a strategy given as a heuristic and a search limit, like `korman@5` for an
+/// alpha-beta search to depth 5, or `stab + cur-mob@100ms` for 100 ms per move.
+pub fn parse_strategy(text: &str) -> Result<StrategySpec, StrategyError> {
+    let (heuristic, limit) = text.rsplit_once('@').ok_or(StrategyError::MissingLimit)?;
+    let heuristic = parse_heuristic(heuristic).map_err(StrategyError::InvalidHeuristic)?;
+    let limit = limit.trim();
+    let invalid = || StrategyError::InvalidLimit(limit.to_string());
+    let limit = match limit.strip_suffix("ms") {
+        Some(time_ms) => match time_ms.trim().parse() {
+            Ok(time_ms) if time_ms > 0 => SearchLimit::TimeMs(time_ms),
+            _ => return Err(invalid()),
+        },
+        None => match limit.parse() {
+            Ok(depth) if (1..=MAX_DEPTH).contains(&depth) => SearchLimit::Depth(depth),
+            _ => return Err(invalid()),
+        },
+    };
+    Ok(StrategySpec { heuristic, limit })
+}
+
 pub static HEURISTIC_LIST: Lazy<String> = Lazy::new(|| {
     let mut text = String::from("Available heuristics:\n");
     for chunk in HeuristicArg::value_variants()
@@ -72,18 +317,160 @@ pub static HEURISTIC_LIST: Lazy<String> = Lazy::new(|| {
         }
         text += "\n";
     }
+    text += "\nHeuristics can be combined into weighted averages, optionally per move range:\n";
+    text += "- 0.8*corn-own + 0.2*cur-mob\n";
+    text += "- 1-20: cur-mob + pot-mob; 21-60: 3*stab + w-korman\n";
+    text += "\nPath of a weights file (8 rows of 8 values, or 10 values for A1-D1-D4) \
+             can be used in place of any name.\n";
+    text += "Within expressions, such paths can't contain '+', '*', ':' or ';'.\n";
     text
 });
 
 pub fn build_strategy(
-    heuristic: HeuristicArg,
+    heuristic: Heuristic,
     depth: u32,
     no_pruning: bool,
+    fast_wins: bool,
 ) -> Box<dyn TreeVisitingStrategy> {
-    let heuristic = heuristic.into();
     if no_pruning {
-        Box::new(Minimax::new(heuristic, depth))
+        Box::new(Minimax::new(heuristic, depth).with_fast_wins(fast_wins))
     } else {
-        Box::new(AlphaBeta::new(heuristic, depth))
+        Box::new(AlphaBeta::new(heuristic, depth).with_fast_wins(fast_wins))
+    }
+}
+
+pub fn build_timed_strategy(
+    heuristic: Heuristic,
+    time_ms: u64,
+    fast_wins: bool,
+) -> Box<dyn TreeVisitingStrategy> {
+    Box::new(
+        IterativeDeepening::new(heuristic, Duration::from_millis(time_ms))
+            .with_fast_wins(fast_wins),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{game::GameState, utils::rng};
+
+    #[test]
+    fn strategies_are_parsed_with_their_limit() {
+        assert_eq!(
+            parse_strategy("korman@5"),
+            Ok(StrategySpec {
+                heuristic: Heuristic::Korman,
+                limit: SearchLimit::Depth(5),
+            })
+        );
+        let spec = parse_strategy("stab + cur-mob @ 100ms").unwrap();
+        assert_eq!(spec.limit, SearchLimit::TimeMs(100));
+        assert_eq!(
+            spec.build(false).to_string(),
+            "ID(Mix(1*Stab + 1*CurMob), 100ms)"
+        );
+        assert_eq!(parse_strategy("korman"), Err(StrategyError::MissingLimit));
+        assert_eq!(
+            parse_strategy("korman@11"),
+            Err(StrategyError::InvalidLimit(String::from("11")))
+        );
+        assert_eq!(
+            parse_strategy("korman@0ms"),
+            Err(StrategyError::InvalidLimit(String::from("0ms")))
+        );
+        assert!(matches!(
+            parse_strategy("unknown@3"),
+            Err(StrategyError::InvalidHeuristic(
+                HeuristicError::UnknownName(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn names_are_parsed_as_presets() {
+        assert_eq!(parse_heuristic("korman"), Ok(Heuristic::Korman));
+        assert_eq!(parse_heuristic(" Corn-Own "), Ok(Heuristic::CornersOwned));
+    }
+
+    #[test]
+    fn expressions_are_parsed_as_weighted_averages() {
+        let heuristic = parse_heuristic("0.8*corn-own + 0.2*cur-mob").unwrap();
+        let expected = Heuristic::Composite(Box::new([Phase {
+            moves: 1..=60,
+            factors: vec![
+                (0.8, Heuristic::CornersOwned),
+                (0.2, Heuristic::CurrentMobility),
+            ],
+        }]));
+        assert_eq!(heuristic, expected);
+        assert_eq!(heuristic.to_string(), "Mix(0.8*CrOwn + 0.2*CurMob)");
+    }
+
+    #[test]
+    fn phases_are_chosen_by_move_number() {
+        rng::seed(18);
+        let heuristic = parse_heuristic("stab; 1-20: 2*cur-mob + pot-mob").unwrap();
+        assert_eq!(
+            heuristic.to_string(),
+            "Mix(1-20: 2*CurMob + 1*PotMob; 1*Stab)"
+        );
+        let opening = GameState::random_state_between_inc(10, 10);
+        let midgame = GameState::random_state_between_inc(40, 40);
+        let expected_opening = (2. * Heuristic::CurrentMobility.evaluate(&opening)
+            + Heuristic::PotentialMobility.evaluate(&opening))
+            / 3.;
+        assert_eq!(heuristic.evaluate(&opening), expected_opening);
+        assert_eq!(
+            heuristic.evaluate(&midgame),
+            Heuristic::Stability.evaluate(&midgame)
+        );
+    }
+
+    #[test]
+    fn weight_files_can_be_used_as_names() {
+        let path = std::env::temp_dir().join(format!("weights-{}.txt", std::process::id()));
+        fs::write(&path, "64 -30 10 5 -40 2 2 5 1 1").unwrap();
+        let path = path.to_str().unwrap();
+
+        let heuristic = parse_heuristic(path).unwrap();
+        let name = format!("weights-{}", std::process::id());
+        assert_eq!(heuristic.to_string(), format!("W({name})"));
+        let gs = GameState::random_state_between_inc(30, 30);
+        assert_eq!(heuristic.evaluate(&gs), Heuristic::W_MAGGS.evaluate(&gs));
+
+        let mixed = parse_heuristic(&format!("1-30: 2*{path} + stab; korman")).unwrap();
+        assert_eq!(
+            mixed.to_string(),
+            format!("Mix(1-30: 2*W({name}) + 1*Stab; 1*KORMAN)")
+        );
+
+        fs::write(path, "1 2 3").unwrap();
+        assert_eq!(
+            parse_heuristic(path),
+            Err(HeuristicError::InvalidWeights(
+                path.to_string(),
+                WeightsError::WrongCount(3)
+            ))
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        use HeuristicError::*;
+        let error = |text| parse_heuristic(text).unwrap_err();
+        assert_eq!(error(""), Empty);
+        assert_eq!(error("0.5*corn-own +"), Empty);
+        assert_eq!(error("korman2"), UnknownName("korman2".to_string()));
+        assert_eq!(error("-1*korman"), InvalidWeight("-1".to_string()));
+        assert_eq!(error("x*korman"), InvalidWeight("x".to_string()));
+        assert_eq!(
+            error("0-20: korman; stab"),
+            InvalidRange("0-20".to_string())
+        );
+        assert_eq!(error("1-20: korman; 20-60: stab"), OverlappingRanges(20));
+        assert_eq!(error("1-20: korman; 22-60: stab"), UncoveredMove(21));
+        assert_eq!(error("korman + stab; iago"), DuplicateDefault);
     }
 }
@@ -1,4 +1,7 @@
 pub mod cli;
 pub mod elo;
+pub mod rating;
+pub mod rng;
+pub mod sprt;
 pub mod styles;
 pub mod tournament;
@@ -0,0 +1,399 @@
+use crate::game::{Outcome, Player};
+
+/// Virtual draw added between every pair that met, keeps perfect scores at a finite rating.
+const PRIOR_DRAWS: f64 = 1.;
+const MAX_ITERATIONS: usize = 10_000;
+const TOLERANCE: f64 = 1e-10;
+/// Standard normal quantile of 97.5%, for two-sided 95% confidence intervals.
+const Z_95: f64 = 1.959_964;
+
+/// Elo points per unit of the natural logarithm of strength.
+fn elo_scale() -> f64 {
+    400. / 10f64.ln()
+}
+
+/// Games played between every pair of competitors.
+#[must_use]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Results {
+    /// Games between the competitors, symmetric.
+    games: Vec<Vec<f64>>,
+    /// Points scored by the first competitor against the second, a draw is worth half.
+    points: Vec<Vec<f64>>,
+    draws: Vec<u32>,
+}
+
+impl Results {
+    pub fn new(competitors: usize) -> Self {
+        Self {
+            games: vec![vec![0.; competitors]; competitors],
+            points: vec![vec![0.; competitors]; competitors],
+            draws: vec![0; competitors],
+        }
+    }
+
+    /// Collects games as sent by the tournament runners.
+    pub fn from_games(
+        competitors: usize,
+        games: impl IntoIterator<Item = (usize, usize, Outcome)>,
+    ) -> Self {
+        let mut results = Self::new(competitors);
+        for (bi, wi, outcome) in games {
+            results.add(bi, wi, &outcome);
+        }
+        results
+    }
+
+    #[must_use]
+    pub fn competitors(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn add(&mut self, bi: usize, wi: usize, outcome: &Outcome) {
+        let black_points = match outcome {
+            Outcome::Winner(Player::Black) => 1.,
+            Outcome::Winner(Player::White) => 0.,
+            Outcome::Draw => {
+                self.draws[bi] += 1;
+                self.draws[wi] += 1;
+                0.5
+            }
+        };
+        self.games[bi][wi] += 1.;
+        self.games[wi][bi] += 1.;
+        self.points[bi][wi] += black_points;
+        self.points[wi][bi] += 1. - black_points;
+    }
+
+    #[must_use]
+    pub fn games(&self, i: usize) -> u32 {
+        self.games[i].iter().sum::<f64>() as u32
+    }
+
+    #[must_use]
+    pub fn draws(&self, i: usize) -> u32 {
+        self.draws[i]
+    }
+
+    /// Points scored by the competitor, a draw is worth half a point.
+    #[must_use]
+    pub fn points(&self, i: usize) -> f64 {
+        self.points[i].iter().sum()
+    }
+
+    /// Fits the Bradley-Terry model to all results at once, so their order doesn't matter.
+    ///
+    /// As in BayesElo, a virtual draw is added to every pair that met. Ratings are relative to
+    /// the average competitor. Competitors not connected by games to all others can't be
+    /// compared, their margins are infinite and their likelihoods of superiority 0.5.
+    pub fn fit(&self) -> RatingTable {
+        let n = self.competitors();
+        let mut games = self.games.clone();
+        let mut points = self.points.clone();
+        for i in 0..n {
+            for j in 0..n {
+                if games[i][j] > 0. {
+                    games[i][j] += PRIOR_DRAWS;
+                    points[i][j] += PRIOR_DRAWS / 2.;
+                }
+            }
+        }
+
+        // Minorization-maximization iterations of Hunter (2004) on the strengths
+        let mut strengths = vec![1.; n];
+        for _ in 0..MAX_ITERATIONS {
+            let mut change: f64 = 0.;
+            for i in 0..n {
+                let won = points[i].iter().sum::<f64>();
+                let expected = (0..n)
+                    .map(|j| games[i][j] / (strengths[i] + strengths[j]))
+                    .sum::<f64>();
+                if won > 0. && expected > 0. {
+                    let updated = won / expected;
+                    change = change.max((updated / strengths[i]).ln().abs());
+                    strengths[i] = updated;
+                }
+            }
+            if change < TOLERANCE {
+                break;
+            }
+        }
+
+        let log_strengths = strengths.iter().map(|s| s.ln()).collect::<Vec<_>>();
+        let mean = log_strengths.iter().sum::<f64>() / n as f64;
+        let thetas = log_strengths.iter().map(|s| s - mean).collect::<Vec<_>>();
+
+        // Fisher information of the log strengths, its rows sum to zero
+        let mut information = vec![vec![0.; n]; n];
+        for i in 0..n {
+            for j in 0..n {
+                if i != j && games[i][j] > 0. {
+                    let p = 1. / (1. + (thetas[j] - thetas[i]).exp());
+                    let value = games[i][j] * p * (1. - p);
+                    information[i][j] -= value;
+                    information[i][i] += value;
+                }
+            }
+        }
+        // Ratings are relative to their mean, which makes the information matrix invertible
+        for row in information.iter_mut() {
+            for value in row.iter_mut() {
+                *value += 1. / n as f64;
+            }
+        }
+        let covariance = invert(information).map(|mut inverse| {
+            for row in inverse.iter_mut() {
+                for value in row.iter_mut() {
+                    *value -= 1. / n as f64;
+                }
+            }
+            inverse
+        });
+
+        let ratings = (0..n)
+            .map(|i| Rating {
+                elo: elo_scale() * thetas[i],
+                margin: covariance.as_ref().map_or(f64::INFINITY, |c| {
+                    Z_95 * elo_scale() * c[i][i].max(0.).sqrt()
+                }),
+                games: self.games(i),
+                draws: self.draws(i),
+                points: self.points(i),
+            })
+            .collect();
+        let los = (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|j| match &covariance {
+                        Some(c) if i != j => {
+                            let deviation = (c[i][i] + c[j][j] - 2. * c[i][j]).max(0.).sqrt();
+                            normal_cdf((thetas[i] - thetas[j]) / deviation)
+                        }
+                        _ => 0.5,
+                    })
+                    .collect()
+            })
+            .collect();
+        RatingTable { ratings, los }
+    }
+}
+
+/// Fitted rating of a single competitor.
+#[must_use]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rating {
+    /// Elo relative to the average competitor.
+    pub elo: f64,
+    /// Half width of the 95% confidence interval of the Elo.
+    pub margin: f64,
+    pub games: u32,
+    pub draws: u32,
+    /// Points scored, a draw is worth half a point.
+    pub points: f64,
+}
+
+impl Rating {
+    /// Share of the available points scored.
+    #[must_use]
+    pub fn score(&self) -> f64 {
+        self.points / self.games as f64
+    }
+}
+
+/// Ratings of all competitors with the likelihood of superiority between each pair.
+#[must_use]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RatingTable {
+    pub ratings: Vec<Rating>,
+    /// Probability that the first competitor is stronger than the second one.
+    pub los: Vec<Vec<f64>>,
+}
+
+impl RatingTable {
+    /// Competitors from the highest rated to the lowest.
+    #[must_use]
+    pub fn ranking(&self) -> Vec<usize> {
+        let mut indices = (0..self.ratings.len()).collect::<Vec<_>>();
+        indices.sort_by(|&a, &b| self.ratings[b].elo.total_cmp(&self.ratings[a].elo));
+        indices
+    }
+}
+
+/// Gauss-Jordan elimination with partial pivoting, `None` if the matrix is singular.
+fn invert(mut matrix: Vec<Vec<f64>>) -> Option<Vec<Vec<f64>>> {
+    let n = matrix.len();
+    let mut inverse = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| if i == j { 1. } else { 0. })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    for col in 0..n {
+        let pivot =
+            (col..n).max_by(|&a, &b| matrix[a][col].abs().total_cmp(&matrix[b][col].abs()))?;
+        if matrix[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        matrix.swap(col, pivot);
+        inverse.swap(col, pivot);
+
+        let factor = matrix[col][col];
+        for j in 0..n {
+            matrix[col][j] /= factor;
+            inverse[col][j] /= factor;
+        }
+        for row in 0..n {
+            let factor = matrix[row][col];
+            if row == col || factor == 0. {
+                continue;
+            }
+            for j in 0..n {
+                matrix[row][j] -= factor * matrix[col][j];
+                inverse[row][j] -= factor * inverse[col][j];
+            }
+        }
+    }
+    Some(inverse)
+}
+
+/// Standard normal distribution function, using the erf approximation 7.1.26 of Abramowitz
+/// and Stegun, which is accurate to 1.5e-7.
+fn normal_cdf(x: f64) -> f64 {
+    if x.is_nan() {
+        return 0.5;
+    }
+    let z = x.abs() / 2f64.sqrt();
+    let t = 1. / (1. + 0.327_591_1 * z);
+    let polynomial = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let erf = 1. - polynomial * (-z * z).exp();
+    if x >= 0. {
+        (1. + erf) / 2.
+    } else {
+        (1. - erf) / 2.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    fn outcome(code: u8) -> Outcome {
+        match code % 3 {
+            0 => Outcome::Winner(Player::Black),
+            1 => Outcome::Winner(Player::White),
+            _ => Outcome::Draw,
+        }
+    }
+
+    fn repeated(games: &[(usize, usize, u8)], times: usize) -> Vec<(usize, usize, Outcome)> {
+        games
+            .iter()
+            .cycle()
+            .take(games.len() * times)
+            .map(|&(bi, wi, code)| (bi, wi, outcome(code)))
+            .collect()
+    }
+
+    #[quickcheck]
+    fn order_of_games_does_not_matter(codes: Vec<u8>) -> bool {
+        let games = codes
+            .iter()
+            .enumerate()
+            .map(|(i, &code)| (i % 3, (i + 1 + code as usize % 2) % 3, outcome(code)))
+            .collect::<Vec<_>>();
+        let forward = Results::from_games(3, games.iter().copied());
+        let backward = Results::from_games(3, games.iter().rev().copied());
+        let (forward, backward) = (forward.fit(), backward.fit());
+        forward
+            .ratings
+            .iter()
+            .zip(&backward.ratings)
+            .all(|(a, b)| (a.elo - b.elo).abs() < 1e-6)
+    }
+
+    #[test]
+    fn draws_count_as_half_points() {
+        // One win each and two draws is an even score
+        let even = Results::from_games(
+            2,
+            repeated(&[(0, 1, 0), (0, 1, 1), (0, 1, 2), (1, 0, 2)], 10),
+        );
+        let table = even.fit();
+        assert!(table.ratings[0].elo.abs() < 1e-6);
+        assert!((table.los[0][1] - 0.5).abs() < 1e-6);
+        assert_eq!(table.ratings[0].draws, 20);
+        assert_eq!(table.ratings[0].score(), 0.5);
+
+        // A win and a draw out of two games is a 75% score, same as three wins out of four
+        let draws = Results::from_games(2, repeated(&[(0, 1, 0), (1, 0, 2)], 100)).fit();
+        let wins = Results::from_games(
+            2,
+            repeated(&[(0, 1, 0), (1, 0, 1), (0, 1, 0), (0, 1, 1)], 50),
+        )
+        .fit();
+        assert!((draws.ratings[0].elo - wins.ratings[0].elo).abs() < 1e-6);
+    }
+
+    #[test]
+    fn ratings_match_the_expected_score() {
+        // Three wins out of four games is 400 * log10(3) Elo ahead, split around the mean
+        let table = Results::from_games(
+            2,
+            repeated(&[(0, 1, 0), (1, 0, 1), (0, 1, 0), (0, 1, 1)], 1000),
+        )
+        .fit();
+        let difference = table.ratings[0].elo - table.ratings[1].elo;
+        assert!(
+            (difference - 400. * 3f64.log10()).abs() < 0.2,
+            "{difference}"
+        );
+        assert!((table.ratings[0].elo + table.ratings[1].elo).abs() < 1e-6);
+        assert!(table.los[0][1] > 0.999);
+        assert!((table.los[0][1] + table.los[1][0] - 1.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn margins_shrink_with_more_games() {
+        let games = [
+            (0, 1, 0),
+            (1, 0, 0),
+            (1, 2, 0),
+            (2, 1, 2),
+            (0, 2, 0),
+            (2, 0, 1),
+        ];
+        let few = Results::from_games(3, repeated(&games, 5)).fit();
+        let many = Results::from_games(3, repeated(&games, 500)).fit();
+        for (few, many) in few.ratings.iter().zip(&many.ratings) {
+            assert!(few.margin.is_finite());
+            assert!(many.margin < few.margin / 5.);
+        }
+    }
+
+    #[test]
+    fn perfect_scores_have_finite_ratings() {
+        let table = Results::from_games(3, repeated(&[(0, 1, 0), (1, 2, 0)], 10)).fit();
+        assert_eq!(table.ranking(), [0, 1, 2]);
+        assert!(table.ratings.iter().all(|r| r.elo.is_finite()));
+        assert!(table.los[0][2] > table.los[0][1]);
+    }
+
+    #[test]
+    fn unconnected_competitors_can_not_be_compared() {
+        let table = Results::from_games(4, repeated(&[(0, 1, 0), (2, 3, 0)], 10)).fit();
+        assert!(table.ratings.iter().all(|r| r.margin.is_infinite()));
+        assert_eq!(table.los[0][2], 0.5);
+    }
+
+    #[test]
+    fn normal_distribution_function() {
+        assert!((normal_cdf(0.) - 0.5).abs() < 1e-7);
+        assert!((normal_cdf(Z_95) - 0.975).abs() < 1e-6);
+        assert!((normal_cdf(-1.) - 0.158_655_3).abs() < 1e-6);
+    }
+}
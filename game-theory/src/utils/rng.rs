@@ -0,0 +1,111 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{cell::RefCell, sync::Mutex};
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// Seeds the generator of the current thread.
+///
+/// Everything random done afterwards on this thread repeats between runs, this includes
+/// random strategies, generated game states and the games of tournaments started here.
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+/// Runs the closure with the generator of the current thread.
+///
+/// # Panics
+///
+/// If called again from inside the closure.
+pub fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+/// Draws a seed from the generator of the current thread, for work done elsewhere.
+#[must_use]
+pub fn next_seed() -> u64 {
+    with_rng(|rng| rng.gen())
+}
+
+/// Generator of a strategy, which draws from the generator of the current thread until seeded.
+///
+/// A seeded strategy repeats its choices when used from one thread at a time. Shared between
+/// games played in parallel, it draws in whatever order the threads get to it, while the
+/// generator of each thread is seeded per game by the tournaments.
+#[derive(Debug, Default)]
+pub struct StrategyRng(Option<Mutex<StdRng>>);
+
+impl StrategyRng {
+    /// Draws from the generator of the current thread.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(None)
+    }
+
+    /// Draws from its own generator with the given seed.
+    #[must_use]
+    pub fn seeded(seed: u64) -> Self {
+        Self(Some(Mutex::new(StdRng::seed_from_u64(seed))))
+    }
+
+    /// Runs the closure with the generator.
+    ///
+    /// # Panics
+    ///
+    /// If called again from inside the closure, which may also deadlock when seeded.
+    pub fn with<T>(&self, f: impl FnOnce(&mut StdRng) -> T) -> T {
+        match &self.0 {
+            Some(rng) => f(&mut rng.lock().unwrap()),
+            None => with_rng(f),
+        }
+    }
+}
+
+/// Derives the seed of one of many independent streams, such as the games of a tournament.
+#[must_use]
+pub const fn stream_seed(seed: u64, stream: u64) -> u64 {
+    seed ^ stream.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ai::{RandomMove, Strategy},
+        game::GameState,
+    };
+    use quickcheck_macros::quickcheck;
+    use std::thread;
+
+    fn random_game() -> Vec<String> {
+        let mut gs = GameState::random_state_between_inc(1, 10);
+        let mut moves = vec![gs.to_string()];
+        while gs.outcome().is_none() {
+            let position = RandomMove::new().decide(&gs);
+            moves.push(position.to_string());
+            gs = gs.make_move(position);
+        }
+        moves
+    }
+
+    #[quickcheck]
+    fn same_seed_gives_the_same_game(value: u64) -> bool {
+        seed(value);
+        let first = random_game();
+        seed(value);
+        first == random_game()
+    }
+
+    #[test]
+    fn seeds_are_independent_between_threads() {
+        seed(7);
+        let here = random_game();
+        let there = thread::spawn(|| {
+            seed(7);
+            random_game()
+        });
+        assert_eq!(here, there.join().unwrap());
+        assert_ne!(stream_seed(7, 0), stream_seed(7, 1));
+    }
+}
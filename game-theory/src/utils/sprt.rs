@@ -0,0 +1,231 @@
+use crate::{
+    ai::Strategy,
+    game::{GameState, Outcome, Player},
+    utils::tournament::{run_schedule, Schedule, TournamentGame},
+};
+use std::fmt::{self, Display};
+
+/// Hypothesis accepted by the test.
+#[must_use]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hypothesis {
+    /// The candidate is at most `elo0` stronger than the baseline.
+    H0,
+    /// The candidate is at least `elo1` stronger than the baseline.
+    H1,
+}
+
+/// Results of the candidate so far, with the log-likelihood ratio of H1 to H0.
+#[must_use]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SprtStatus {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    pub llr: f64,
+}
+
+impl SprtStatus {
+    #[must_use]
+    pub const fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+}
+
+impl Display for SprtStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} games (+{} ={} -{}), LLR {:.3}",
+            self.games(),
+            self.wins,
+            self.draws,
+            self.losses,
+            self.llr
+        )
+    }
+}
+
+/// Sequential probability ratio test between two Elo differences.
+#[must_use]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    /// Probability of accepting H1 when H0 holds.
+    pub alpha: f64,
+    /// Probability of accepting H0 when H1 holds.
+    pub beta: f64,
+}
+
+/// Expected score of a player stronger by the given Elo difference.
+fn expected_score(elo: f64) -> f64 {
+    1. / (1. + 10f64.powf(-elo / 400.))
+}
+
+impl Sprt {
+    pub const fn new(elo0: f64, elo1: f64, alpha: f64, beta: f64) -> Self {
+        Self {
+            elo0,
+            elo1,
+            alpha,
+            beta,
+        }
+    }
+
+    /// Log-likelihood ratios at which H0 and H1 are accepted.
+    #[must_use]
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1. - self.alpha)).ln(),
+            ((1. - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// Log-likelihood ratio of the results, approximating the score by a normal distribution
+    /// with the variance observed in the games, as done by cutechess-cli.
+    ///
+    /// Until the games differ in their results, the variance of decisive games with the score
+    /// halfway between the hypotheses is assumed.
+    #[must_use]
+    pub fn llr(&self, wins: u32, draws: u32, losses: u32) -> f64 {
+        if wins + draws + losses == 0 {
+            return 0.;
+        }
+        let games = (wins + draws + losses) as f64;
+        let (w, d, l) = (
+            wins as f64 / games,
+            draws as f64 / games,
+            losses as f64 / games,
+        );
+        let score = w + d / 2.;
+        let (s0, s1) = (expected_score(self.elo0), expected_score(self.elo1));
+        let mut variance = w * (1. - score).powi(2) + d * (0.5 - score).powi(2) + l * score.powi(2);
+        if variance == 0. {
+            let halfway = (s0 + s1) / 2.;
+            variance = halfway * (1. - halfway);
+        }
+        games * (s1 - s0) * (2. * score - s0 - s1) / (2. * variance)
+    }
+
+    /// Accepted hypothesis for the given log-likelihood ratio, if any.
+    pub fn decide(&self, llr: f64) -> Option<Hypothesis> {
+        let (lower, upper) = self.bounds();
+        if llr <= lower {
+            Some(Hypothesis::H0)
+        } else if llr >= upper {
+            Some(Hypothesis::H1)
+        } else {
+            None
+        }
+    }
+
+    /// Plays batches of paired games until a hypothesis is accepted, or `max_games` is reached.
+    ///
+    /// Every batch plays each of the given openings twice, with colours swapped, and is
+    /// reported with its games once finished.
+    pub fn run(
+        &self,
+        candidate: &dyn Strategy,
+        baseline: &dyn Strategy,
+        mut openings: impl FnMut() -> Vec<GameState>,
+        max_games: Option<u32>,
+        mut report: impl FnMut(&SprtStatus, &[TournamentGame]),
+    ) -> Option<Hypothesis> {
+        let competitors = [candidate, baseline];
+        let mut status = SprtStatus::default();
+        while max_games.is_none_or(|max| status.games() < max) {
+            let rx = run_schedule(2, Schedule::RoundRobin, &openings(), |i| competitors[i]);
+            let games = rx.iter().collect::<Vec<_>>();
+            for game in &games {
+                let candidate_color = if game.black == 0 {
+                    Player::Black
+                } else {
+                    Player::White
+                };
+                match game.outcome() {
+                    Outcome::Winner(winner) if winner == candidate_color => status.wins += 1,
+                    Outcome::Winner(_) => status.losses += 1,
+                    Outcome::Draw => status.draws += 1,
+                }
+            }
+            status.llr = self.llr(status.wins, status.draws, status.losses);
+            report(&status, &games);
+
+            if let Some(hypothesis) = self.decide(status.llr) {
+                return Some(hypothesis);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ai::{AlphaBeta, Heuristic, RandomMove},
+        utils::{rng, tournament::random_openings},
+    };
+
+    #[test]
+    fn bounds_follow_the_error_rates() {
+        let (lower, upper) = Sprt::new(0., 5., 0.05, 0.05).bounds();
+        assert!((lower + 2.944).abs() < 1e-3);
+        assert!((upper - 2.944).abs() < 1e-3);
+    }
+
+    #[test]
+    fn llr_favours_the_closer_hypothesis() {
+        let sprt = Sprt::new(0., 100., 0.05, 0.05);
+        // Expected score at 100 Elo is 64%
+        assert!(sprt.llr(640, 0, 360) > 0.);
+        assert!(sprt.llr(500, 0, 500) < 0.);
+        // Halfway between the hypotheses, both are equally likely
+        let halfway = (expected_score(0.) + expected_score(100.)) / 2.;
+        let wins = (halfway * 100_000.).round() as u32;
+        assert!(sprt.llr(wins, 0, 100_000 - wins).abs() < 1.);
+        // Evidence grows with the number of games
+        assert!(sprt.llr(1280, 0, 720) > 2. * sprt.llr(640, 0, 360) - 1e-9);
+        assert_eq!(sprt.llr(0, 0, 0), 0.);
+        // Decisive results are evidence even before the first loss
+        assert!(sprt.llr(10, 0, 0) > 0.);
+        assert!(sprt.llr(0, 0, 10) < 0.);
+    }
+
+    #[test]
+    fn draws_count_as_half_points() {
+        let sprt = Sprt::new(-50., 50., 0.05, 0.05);
+        assert!(sprt.llr(10, 80, 10).abs() < 1e-9);
+        assert!(sprt.llr(20, 70, 10) > 0.);
+    }
+
+    #[test]
+    fn stronger_candidates_are_accepted() {
+        rng::seed(24);
+        let sprt = Sprt::new(0., 200., 0.05, 0.05);
+        let candidate = AlphaBeta::new(Heuristic::W_KORMAN, 2);
+        let mut reports = 0;
+        let result = sprt.run(
+            &candidate,
+            &RandomMove::new(),
+            || random_openings(4, 4),
+            Some(400),
+            |status, games| {
+                reports += 1;
+                assert_eq!(status.games(), reports * 8);
+                assert_eq!(games.len(), 8);
+            },
+        );
+        assert_eq!(result, Some(Hypothesis::H1));
+
+        let result = sprt.run(
+            &RandomMove::new(),
+            &candidate,
+            || random_openings(4, 4),
+            Some(400),
+            |_, _| {},
+        );
+        assert_eq!(result, Some(Hypothesis::H0));
+    }
+}
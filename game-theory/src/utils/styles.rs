@@ -43,20 +43,29 @@ pub const VALID_FG: Color = Color::TrueColor {
 };
 
 #[must_use]
-pub fn strip_string(input: &str) -> String {
+pub fn strip_ansi(input: &str) -> String {
     let mut result = String::with_capacity(input.len());
     let mut chars = input.chars();
-    while let Some(mut char) = chars.next() {
-        // Skip ANSI escape codes
+    while let Some(char) = chars.next() {
         if char == '\u{1b}' {
-            while char != 'm' {
-                char = chars.next().unwrap_or('m');
+            // Skip the whole escape code, which ends with 'm'
+            for char in chars.by_ref() {
+                if char == 'm' {
+                    break;
+                }
             }
-        }
-        // Accept only 0, 1 and 2
-        if matches!(char, '0' | '1' | '2') {
+        } else {
             result.push(char);
         }
     }
     result
 }
+
+#[must_use]
+pub fn strip_string(input: &str) -> String {
+    // Accept only 0, 1 and 2
+    strip_ansi(input)
+        .chars()
+        .filter(|char| matches!(char, '0' | '1' | '2'))
+        .collect()
+}
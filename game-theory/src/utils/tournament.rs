@@ -1,52 +1,443 @@
 use crate::{
-    ai::Strategy,
-    game::{GameState, Outcome, Player},
+    ai::{RandomMove, Strategy},
+    game::{
+        record::{GameRecord, RecordError},
+        zobrist::ZobristBuildHasher,
+        GameState, Outcome, Player,
+    },
+    utils::rng,
 };
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use std::{
-    sync::mpsc::{channel, Receiver},
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{channel, Receiver},
+    },
     thread::{available_parallelism, scope},
     time::{Duration, Instant},
 };
 
-pub fn run_tournament<'a, F: Fn(usize) -> &'a (dyn Strategy) + Sync>(
+/// Which competitors meet, every pairing plays each opening twice with colours swapped.
+#[must_use]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Schedule {
+    /// Every competitor meets every other one.
+    RoundRobin,
+    /// The first competitor meets every other one, who do not meet each other.
+    Gauntlet,
+    /// Competitors with similar scores meet in every round, without rematches if possible.
+    Swiss { rounds: usize },
+}
+
+impl Schedule {
+    /// Number of games played by the schedule, which does not depend on the results.
+    #[must_use]
+    pub const fn games(&self, competitors: usize, openings: usize) -> usize {
+        let pairings = match self {
+            Schedule::RoundRobin => competitors * competitors.saturating_sub(1) / 2,
+            Schedule::Gauntlet => competitors.saturating_sub(1),
+            Schedule::Swiss { rounds } => *rounds * (competitors / 2),
+        };
+        2 * openings * pairings
+    }
+}
+
+fn threads() -> usize {
+    available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// Finished game of a tournament, between the competitors with the given indices.
+#[must_use]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TournamentGame {
+    pub black: usize,
+    pub white: usize,
+    /// Moves from the opening with their times, named after the strategies.
+    pub record: GameRecord,
+}
+
+impl TournamentGame {
+    pub fn outcome(&self) -> Outcome {
+        self.record
+            .outcome()
+            .expect("Tournament games are played to the end!")
+    }
+}
+
+fn play_game(
+    (bi, wi): (usize, usize),
+    (black, white): (&dyn Strategy, &dyn Strategy),
+    gs: GameState,
+) -> TournamentGame {
+    let mut record = GameRecord::new(gs);
+    record.black_name = Some(black.to_string());
+    record.white_name = Some(white.to_string());
+    while record.outcome().is_none() {
+        let strategy = match record.end().turn() {
+            Player::Black => black,
+            Player::White => white,
+        };
+        let start = Instant::now();
+        let position = strategy.decide(record.end());
+        record
+            .play_timed(position, start.elapsed())
+            .expect("Strategies only play valid moves!");
+    }
+    TournamentGame {
+        black: bi,
+        white: wi,
+        record,
+    }
+}
+
+/// Plays random pairings from random openings until the timeout, on every available thread.
+///
+/// Every finished game is sent with its full record, including the time of every move.
+///
+/// Each thread gets its own stream, seeded from the generator of the calling thread, so
+/// seeding it beforehand repeats the games of every thread in the same order. How many games
+/// are played before the timeout still varies, so runs that have to repeat exactly should use
+/// `run_schedule`, which plays a fixed number of games.
+pub fn run_tournament<'a, F: Sync + Fn(usize) -> &'a dyn Strategy>(
     competitors: usize,
     timeout: Duration,
     selector: F,
-) -> Receiver<(usize, usize, Outcome)> {
+) -> Receiver<TournamentGame> {
     let (tx, rx) = channel();
-    let threads = available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let seed = rng::next_seed();
     let start = Instant::now();
     scope(|s| {
-        for _ in 0..threads {
+        for thread in 0..threads() {
             let tx = tx.clone();
             let selector = &selector;
             s.spawn(move || {
+                rng::seed(rng::stream_seed(seed, thread as u64));
                 while start.elapsed() <= timeout {
-                    let bi = thread_rng().gen_range(0..competitors);
-                    let wi = thread_rng().gen_range(0..competitors);
-                    let mut gs = GameState::random_state_between_inc(3, 5);
+                    let (bi, wi) = rng::with_rng(|rng| {
+                        (rng.gen_range(0..competitors), rng.gen_range(0..competitors))
+                    });
+                    let gs = GameState::random_state_between_inc(3, 5);
                     if gs.outcome().is_some() || bi == wi {
                         continue;
                     }
 
-                    let bs = selector(bi);
-                    let ws = selector(wi);
-
-                    while gs.outcome().is_none() {
-                        let position = match gs.turn() {
-                            Player::Black => bs,
-                            Player::White => ws,
-                        }
-                        .decide(&gs);
+                    let game = play_game((bi, wi), (selector(bi), selector(wi)), gs);
+                    tx.send(game).unwrap();
+                }
+            });
+        }
+    });
+    rx
+}
 
-                        gs = gs.make_move(position);
+/// Plays the given games on every available thread, returns them in the same order.
+///
+/// Every game is seeded by its index, so the moves do not depend on the number of threads.
+fn play_games<'a, F: Sync + Fn(usize) -> &'a dyn Strategy>(
+    games: &[(usize, usize, &GameState)],
+    seed: u64,
+    selector: &F,
+) -> Vec<TournamentGame> {
+    let next = AtomicUsize::new(0);
+    let mut played = scope(|s| {
+        let handles = (0..threads())
+            .map(|_| {
+                s.spawn(|| {
+                    let mut played = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(&(bi, wi, gs)) = games.get(index) else {
+                            return played;
+                        };
+                        rng::seed(rng::stream_seed(seed, index as u64));
+                        let strategies = (selector(bi), selector(wi));
+                        played.push((index, play_game((bi, wi), strategies, gs.clone())));
                     }
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    played.sort_by_key(|&(index, _)| index);
+    played.into_iter().map(|(_, game)| game).collect()
+}
+
+/// Search steps allowed for avoiding rematches, before falling back to the greedy pairing.
+const SWISS_SEARCH_LIMIT: usize = 100_000;
+
+/// Unordered pair of competitors.
+const fn pair(a: usize, b: usize) -> (usize, usize) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Pairs each competitor with the best one not met yet, backtracking if the rest cannot be
+/// paired that way, returns `None` if there is no such pairing or the search runs out.
+fn pair_unmet(
+    unpaired: &[usize],
+    met: &HashSet<(usize, usize)>,
+    budget: &mut usize,
+) -> Option<Vec<(usize, usize)>> {
+    let Some((&first, rest)) = unpaired.split_first().filter(|(_, rest)| !rest.is_empty()) else {
+        return Some(Vec::new());
+    };
+    for (i, &second) in rest.iter().enumerate() {
+        if *budget == 0 {
+            return None;
+        }
+        *budget -= 1;
+        if met.contains(&pair(first, second)) {
+            continue;
+        }
+
+        let mut others = rest.to_vec();
+        others.remove(i);
+        if let Some(mut pairings) = pair_unmet(&others, met, budget) {
+            pairings.insert(0, (first, second));
+            return Some(pairings);
+        }
+    }
+    None
+}
+
+/// Pairs competitors in the order of their scores, each with the best one not met yet.
+///
+/// With an odd number of competitors, one of them sits the round out. Rematches are only
+/// played if every pairing would need one.
+fn swiss_pairings(scores: &[f64], met: &HashSet<(usize, usize)>) -> Vec<(usize, usize)> {
+    let mut ranking = (0..scores.len()).collect::<Vec<_>>();
+    // Stable sort, ties are broken by the order of competitors
+    ranking.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+
+    let mut budget = SWISS_SEARCH_LIMIT;
+    if let Some(pairings) = pair_unmet(&ranking, met, &mut budget) {
+        return pairings;
+    }
+    ranking
+        .chunks_exact(2)
+        .map(|chunk| (chunk[0], chunk[1]))
+        .collect()
+}
 
-                    tx.send((bi, wi, gs.outcome().unwrap())).unwrap();
+/// Plays the schedule with every pairing playing each opening once as either colour.
+///
+/// Unlike `run_tournament`, the number of games is fixed and the games arrive in the
+/// order of the schedule. Every game is seeded from the generator of the calling thread and
+/// its position in the schedule, so seeding it beforehand repeats the whole schedule.
+pub fn run_schedule<'a, F: Sync + Fn(usize) -> &'a dyn Strategy>(
+    competitors: usize,
+    schedule: Schedule,
+    openings: &[GameState],
+    selector: F,
+) -> Receiver<TournamentGame> {
+    let (tx, rx) = channel();
+    let seed = rng::next_seed();
+    let rounds = match schedule {
+        Schedule::RoundRobin => {
+            let pairings = (0..competitors)
+                .flat_map(|a| (a + 1..competitors).map(move |b| (a, b)))
+                .collect();
+            vec![pairings]
+        }
+        Schedule::Gauntlet => vec![(1..competitors).map(|b| (0, b)).collect()],
+        Schedule::Swiss { rounds } => vec![Vec::new(); rounds],
+    };
+
+    let mut scores = vec![0.; competitors];
+    let mut met = HashSet::new();
+    let mut played = 0;
+    for mut pairings in rounds {
+        if let Schedule::Swiss { .. } = schedule {
+            pairings = swiss_pairings(&scores, &met);
+        }
+        met.extend(pairings.iter().map(|&(a, b)| pair(a, b)));
+
+        let games = pairings
+            .iter()
+            .flat_map(|&(a, b)| openings.iter().flat_map(move |gs| [(a, b, gs), (b, a, gs)]))
+            .collect::<Vec<_>>();
+        let seed = rng::stream_seed(seed, played as u64);
+        played += games.len();
+
+        for game in play_games(&games, seed, &selector) {
+            match game.outcome() {
+                Outcome::Winner(Player::Black) => scores[game.black] += 1.,
+                Outcome::Winner(Player::White) => scores[game.white] += 1.,
+                Outcome::Draw => {
+                    scores[game.black] += 0.5;
+                    scores[game.white] += 0.5;
                 }
-            });
+            }
+            tx.send(game).unwrap();
         }
-    });
+    }
     rx
 }
+
+/// Generates distinct unfinished openings, reached by playing the given number of random moves
+/// from the standard Othello position with the generator of the current thread.
+///
+/// # Panics
+///
+/// If there are fewer than `count` such openings.
+#[must_use]
+pub fn random_openings(count: usize, moves: i32) -> Vec<GameState> {
+    let mut openings = Vec::with_capacity(count);
+    let mut seen = HashSet::with_hasher(ZobristBuildHasher::default());
+    let mut attempts = 0;
+    while openings.len() < count {
+        attempts += 1;
+        assert!(attempts <= 1000 * count, "Not enough distinct openings!");
+        let mut gs = GameState::othello_initial();
+        for _ in 0..moves {
+            if gs.outcome().is_some() {
+                break;
+            }
+            gs = gs.make_move(rng::with_rng(|rng| RandomMove::choose(&gs, rng)));
+        }
+        if gs.outcome().is_none() && seen.insert(gs.clone()) {
+            openings.push(gs);
+        }
+    }
+    openings
+}
+
+/// Reads openings given as transcripts played from the standard Othello position.
+pub fn openings_from_transcripts<'a>(
+    transcripts: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<GameState>, RecordError> {
+    transcripts
+        .into_iter()
+        .map(|transcript| {
+            GameRecord::from_transcript(GameState::othello_initial(), transcript)
+                .map(|record| record.end().clone())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::{AlphaBeta, CornersGreedy, FirstMove, Heuristic, RandomMove, ScoreGreedy};
+    use quickcheck_macros::quickcheck;
+
+    fn competitors() -> [Box<dyn Strategy>; 6] {
+        [
+            Box::new(RandomMove::new()),
+            Box::new(FirstMove),
+            Box::new(ScoreGreedy::new()),
+            Box::new(CornersGreedy::new()),
+            Box::new(AlphaBeta::new(Heuristic::MaximumDisc, 1)),
+            Box::new(AlphaBeta::new(Heuristic::Korman, 1)),
+        ]
+    }
+
+    fn play(schedule: Schedule, openings: &[GameState]) -> Vec<(usize, usize, String)> {
+        let competitors = competitors();
+        run_schedule(competitors.len(), schedule, openings, |i| {
+            competitors[i].as_ref()
+        })
+        .iter()
+        .map(|game| (game.black, game.white, game.record.transcript()))
+        .collect()
+    }
+
+    #[test]
+    fn schedules_play_a_fixed_number_of_games() {
+        let openings = random_openings(2, 4);
+        for schedule in [
+            Schedule::RoundRobin,
+            Schedule::Gauntlet,
+            Schedule::Swiss { rounds: 3 },
+        ] {
+            let games = play(schedule, &openings);
+            assert_eq!(games.len(), schedule.games(6, 2), "{schedule:?}");
+        }
+    }
+
+    #[test]
+    fn openings_play_the_given_number_of_moves() {
+        let othello = GameState::othello_initial();
+        for opening in random_openings(10, 5) {
+            assert_eq!(opening.occupied_bb().count_ones(), 9);
+            assert!(othello.occupied_bb() & !opening.occupied_bb() == 0);
+        }
+    }
+
+    #[test]
+    fn pairings_play_both_colours() {
+        let games = play(Schedule::RoundRobin, &random_openings(1, 4));
+        assert_eq!(games.len(), 30);
+        for (bi, wi, _) in &games {
+            assert!(games.iter().any(|(b, w, _)| b == wi && w == bi));
+        }
+        let gauntlet = play(Schedule::Gauntlet, &random_openings(1, 4));
+        assert!(gauntlet.iter().all(|&(bi, wi, _)| bi == 0 || wi == 0));
+    }
+
+    #[test]
+    fn swiss_rounds_avoid_rematches() {
+        let games = play(Schedule::Swiss { rounds: 3 }, &random_openings(1, 4));
+        let mut pairs = games
+            .iter()
+            .map(|&(bi, wi, _)| pair(bi, wi))
+            .collect::<Vec<_>>();
+        pairs.dedup();
+        assert_eq!(pairs.len(), 9);
+        assert_eq!(pairs.iter().collect::<HashSet<_>>().len(), 9);
+    }
+
+    #[test]
+    fn swiss_pairs_by_score() {
+        let scores = [0., 3., 1., 2.];
+        assert_eq!(swiss_pairings(&scores, &HashSet::new()), [(1, 3), (2, 0)]);
+        let met = HashSet::from([(1, 3)]);
+        assert_eq!(swiss_pairings(&scores, &met), [(1, 2), (3, 0)]);
+        // Pairing 1 with 2 would leave 0 and 3 for a rematch
+        let met = HashSet::from([(1, 3), (0, 3)]);
+        assert_eq!(swiss_pairings(&scores, &met), [(1, 0), (3, 2)]);
+        let met = HashSet::from([(0, 1), (0, 2), (0, 3)]);
+        assert_eq!(swiss_pairings(&scores, &met), [(1, 3), (2, 0)]);
+    }
+
+    #[quickcheck]
+    fn same_seed_repeats_the_schedule(value: u64) -> bool {
+        rng::seed(value);
+        let openings = random_openings(1, 6);
+        let first = play(Schedule::Gauntlet, &openings);
+        rng::seed(value);
+        openings == random_openings(1, 6) && first == play(Schedule::Gauntlet, &openings)
+    }
+
+    #[test]
+    fn games_are_recorded_from_their_opening() {
+        let openings = random_openings(2, 4);
+        let competitors = competitors();
+        let rx = run_schedule(2, Schedule::RoundRobin, &openings, |i| {
+            competitors[i].as_ref()
+        });
+        for (game, opening) in rx.iter().zip([0, 0, 1, 1]) {
+            assert_eq!(game.record.start(), &openings[opening]);
+            assert_eq!(game.record.times().len(), game.record.moves().len());
+            assert!(game.record.times().iter().all(Option::is_some));
+            let name = competitors[game.black].to_string();
+            assert_eq!(game.record.black_name, Some(name));
+            assert!(game.record.to_ggf().contains("RE["));
+        }
+    }
+
+    #[test]
+    fn openings_are_read_from_transcripts() {
+        let openings = openings_from_transcripts(["F5", "f5 d6"]).unwrap();
+        assert_eq!(openings[0].move_number(), 2);
+        assert_eq!(openings[1].move_number(), 3);
+        assert!(openings_from_transcripts(["F5F5"]).is_err());
+    }
+}
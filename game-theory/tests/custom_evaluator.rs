@@ -0,0 +1,64 @@
+use game_theory::{
+    ai::{
+        AlphaBeta, Evaluator, Heuristic, IterativeDeepening, Pvs, Strategy, TreeVisitingStrategy,
+    },
+    game::{GameState, Player},
+    utils::tournament::run_tournament,
+};
+use std::{
+    fmt::{self, Display},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Disc difference, defined outside of the crate.
+struct DiscDifference;
+
+impl Display for DiscDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DiscDiff")
+    }
+}
+
+impl Evaluator for DiscDifference {
+    fn evaluate(&self, gs: &GameState) -> f64 {
+        let black = gs.score_of(Player::Black) as f64;
+        let white = gs.score_of(Player::White) as f64;
+        (black - white) / (black + white)
+    }
+}
+
+#[test]
+fn custom_evaluators_are_named_by_display() {
+    let boxed: Box<dyn Evaluator> = Box::new(DiscDifference);
+    assert_eq!(AlphaBeta::new(boxed, 3).to_string(), "αβ(DiscDiff, 3)");
+    let shared: Arc<dyn Evaluator> = Arc::new(DiscDifference);
+    assert_eq!(Pvs::new(shared, 2).to_string(), "PVS(DiscDiff, 2)");
+}
+
+#[test]
+fn custom_evaluators_are_searched_like_built_in_ones() {
+    // Both evaluators order positions the same way, so the searches agree on the best move
+    let custom = AlphaBeta::new(Arc::new(DiscDifference) as Arc<dyn Evaluator>, 3);
+    let built_in = AlphaBeta::new(Heuristic::MaximumDisc, 3);
+    let mut gs = GameState::othello_initial();
+    while gs.outcome().is_none() {
+        let position = custom.decide(&gs);
+        assert_eq!(position, built_in.decide(&gs));
+        gs = gs.make_move(position);
+    }
+    assert!(custom.visited() > 0);
+}
+
+#[test]
+fn custom_evaluators_compete_in_tournaments() {
+    let evaluator: Arc<dyn Evaluator> = Arc::new(DiscDifference);
+    let competitors: [Box<dyn Strategy>; 2] = [
+        Box::new(IterativeDeepening::new(evaluator, Duration::from_millis(1))),
+        Box::new(AlphaBeta::new(Heuristic::Korman, 1)),
+    ];
+    let results = run_tournament(competitors.len(), Duration::from_millis(200), |i| {
+        competitors[i].as_ref()
+    });
+    assert!(results.iter().count() > 0);
+}
@@ -0,0 +1,39 @@
+use game_theory::game::{
+    perft::{perft, PerftCounts},
+    GameState,
+};
+
+fn leaves(gs: &GameState, max_depth: u32) -> Vec<u64> {
+    (1..=max_depth)
+        .map(|depth| perft(gs, depth).leaves)
+        .collect()
+}
+
+#[test]
+fn othello_perft_matches_published_numbers() {
+    // Published numbers, where passes count as plies and finished games stay leaves
+    assert_eq!(
+        leaves(&GameState::othello_initial(), 8),
+        [4, 12, 56, 244, 1396, 8200, 55092, 390216]
+    );
+}
+
+#[test]
+fn othello_perft_counts_first_passes_and_finished_games() {
+    assert_eq!(
+        perft(&GameState::othello_initial(), 9),
+        PerftCounts {
+            leaves: 3005288,
+            passes: 24,
+            finished: 228,
+        }
+    );
+}
+
+#[test]
+fn reversi_perft_starts_with_center_placements() {
+    assert_eq!(
+        leaves(&GameState::reversi_initial(), 9),
+        [4, 12, 24, 24, 96, 320, 1536, 6624, 38208]
+    );
+}
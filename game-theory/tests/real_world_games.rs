@@ -1,16 +1,10 @@
-use game_theory::game::{GameState, Outcome, Player, Position};
+use game_theory::game::{record::GameRecord, GameState, Outcome, Player};
 
 fn from_transcript(transcript: &'static str) -> GameState {
-    let mut gs = GameState::othello_initial();
-    for m in transcript
-        .as_bytes()
-        .chunks_exact(2)
-        .map(|c| Position::from(std::str::from_utf8(c).unwrap()).unwrap())
-    {
-        assert_eq!(gs.outcome(), None);
-        gs = gs.make_move(m);
-    }
-    gs
+    GameRecord::from_transcript(GameState::othello_initial(), transcript)
+        .unwrap()
+        .end()
+        .clone()
 }
 
 fn assert_outcome(gs: &GameState, outcome: Outcome, black: u32, white: u32) {
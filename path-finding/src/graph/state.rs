@@ -33,13 +33,13 @@ impl<C: Cost> Eq for State<C> {}
 // https://doc.rust-lang.org/std/collections/binary_heap/index.html
 impl<C: Cost> Ord for State<C> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
+        other.cost.partial_cmp(&self.cost).unwrap()
     }
 }
 
 impl<C: Cost> PartialOrd for State<C> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        other.cost.partial_cmp(&self.cost)
+        Some(self.cmp(other))
     }
 }
 